@@ -261,6 +261,19 @@ impl TryFrom<&[u8]> for AssetCatalog {
 }
 
 impl AssetCatalog {
+    /// Parses `data` into the catalog shared by the whole program, once.
+    pub fn init(data: &[u8]) -> io::Result<&'static AssetCatalog> {
+        if let Some(catalog) = CATALOG.get() {
+            return Ok(catalog);
+        }
+        let catalog = AssetCatalog::try_from(data)?;
+        Ok(CATALOG.get_or_init(|| catalog))
+    }
+
+    pub fn get() -> Option<&'static AssetCatalog> {
+        CATALOG.get()
+    }
+
     pub fn get_asset_info_by_id<T>(&'static self, id: T) -> io::Result<&AssetInfo>
     where
         T: AsRef<AssetId>,
@@ -14,6 +14,12 @@ pub struct AssetId {
 
 impl AssetId {}
 
+impl AsRef<AssetId> for AssetId {
+    fn as_ref(&self) -> &AssetId {
+        self
+    }
+}
+
 impl TryFrom<&[u8]> for AssetId {
     type Error = TryFromSliceError;
 
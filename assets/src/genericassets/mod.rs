@@ -0,0 +1,716 @@
+//! Typed models of the ObjectStream databases in `sharedassets/genericassets`, and flat tables
+//! of their entries for export.
+//!
+//! Fields are typed from the reflected classes, anything else an entry holds (unresolved names,
+//! nested classes, containers) is kept in its `extra` map so no column is lost when exporting.
+
+use file_system::FileSystem;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::HashMap, io, path::Path};
+
+/// A genericassets database with the list of entries it's made of.
+pub trait Database: DeserializeOwned {
+    /// Path of the database in the paks.
+    const PATH: &'static str;
+    type Entry: Serialize;
+
+    fn entries(&self) -> &[Self::Entry];
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FuelCategoryDatabase {
+    #[serde(rename = "Fuel Category Data", default)]
+    pub categories: Vec<FuelCategoryData>,
+}
+
+/// The reflected fields of the class aren't known yet, they all end up in `extra`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FuelCategoryData {
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Database for FuelCategoryDatabase {
+    const PATH: &'static str = "sharedassets/genericassets/fuelcategory.fueldb";
+    type Entry = FuelCategoryData;
+
+    fn entries(&self) -> &[FuelCategoryData] {
+        &self.categories
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerBaseAttributes {
+    #[serde(rename = "Player Attribute Data", default)]
+    pub attributes: PlayerAttributeData,
+}
+
+/// The player tuning values, a single entry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerAttributeData {
+    #[serde(
+        rename = "Base Amount To Apply",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub base_amount_to_apply: Option<i32>,
+    #[serde(
+        rename = "Base Apply Rate",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub base_apply_rate: Option<f32>,
+    #[serde(
+        rename = "Structure Rotation Amount ( Deg )",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub structure_rotation_amount_deg: Option<f32>,
+    #[serde(
+        rename = "Base Gather Durability Cost",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub base_gather_durability_cost: Option<f32>,
+    #[serde(
+        rename = "Repair Max Durability Cost",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub repair_max_durability_cost: Option<f32>,
+    #[serde(
+        rename = "One Handed Gathering Distance",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub one_handed_gathering_distance: Option<f32>,
+    #[serde(
+        rename = "Two Handed Gathering Distance",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub two_handed_gathering_distance: Option<f32>,
+    #[serde(
+        rename = "Stamina Cost Entry",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stamina_cost_entry: Option<String>,
+    #[serde(
+        rename = "Salvage Min Percent",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub salvage_min_percent: Option<f32>,
+    #[serde(
+        rename = "Salvage Max Percent",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub salvage_max_percent: Option<f32>,
+    #[serde(
+        rename = "Chance of Salvage Success",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub chance_of_salvage_success: Option<f32>,
+    #[serde(
+        rename = "Minimum Salvage Quantity",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub minimum_salvage_quantity: Option<i32>,
+    #[serde(
+        rename = "Salvage Dust Modifier",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub salvage_dust_modifier: Option<f32>,
+    #[serde(
+        rename = "Repair Resource Modifier",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub repair_resource_modifier: Option<f32>,
+    #[serde(
+        rename = "User Camera Min Sensitivity",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub user_camera_min_sensitivity: Option<f32>,
+    #[serde(
+        rename = "User Camera Max Sensitivity",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub user_camera_max_sensitivity: Option<f32>,
+    #[serde(
+        rename = "Base Deployable Limit",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub base_deployable_limit: Option<i32>,
+    #[serde(
+        rename = "Player Age Display String",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub player_age_display_string: Option<String>,
+    #[serde(
+        rename = "Encumbrance Immobilization Modifier",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub encumbrance_immobilization_modifier: Option<f32>,
+    #[serde(
+        rename = "Encumbrance Max Limit Modifier",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub encumbrance_max_limit_modifier: Option<f32>,
+    #[serde(
+        rename = "Max Instanced Loot Chest Count",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_instanced_loot_chest_count: Option<i32>,
+    #[serde(
+        rename = "Instanced Loot Chest Reset Time Mins",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub instanced_loot_chest_reset_time_mins: Option<i32>,
+    #[serde(
+        rename = "Instanced AI Loot Clear Time Mins",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub instanced_ai_loot_clear_time_mins: Option<i32>,
+    #[serde(
+        rename = "Max AI Loot Receiver Count",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_ai_loot_receiver_count: Option<i32>,
+    #[serde(
+        rename = "Min Level Roll Perks",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_level_roll_perks: Option<i32>,
+    #[serde(
+        rename = "Min Level Roll Gem Slot",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_level_roll_gem_slot: Option<i32>,
+    #[serde(
+        rename = "Drop Probability Falloff",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub drop_probability_falloff: Option<f32>,
+    #[serde(
+        rename = "Drop Probability Min",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub drop_probability_min: Option<f32>,
+    #[serde(
+        rename = "Azoth Currency",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub azoth_currency: Option<String>,
+    #[serde(
+        rename = "Durability To Coin Rate",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub durability_to_coin_rate: Option<f32>,
+    #[serde(
+        rename = "Inventory Durability Loss Ratio",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub inventory_durability_loss_ratio: Option<f32>,
+    #[serde(
+        rename = "Chat Max Message Size",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub chat_max_message_size: Option<u32>,
+    #[serde(
+        rename = "Min Armor Mitigation",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_armor_mitigation: Option<f32>,
+    #[serde(
+        rename = "Max Armor Mitigation",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_armor_mitigation: Option<f32>,
+    #[serde(
+        rename = "Physical Armor Scale Factor",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub physical_armor_scale_factor: Option<f32>,
+    #[serde(
+        rename = "Elemental Armor Scale Factor",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub elemental_armor_scale_factor: Option<f32>,
+    #[serde(
+        rename = "Armor Set Rating Exponent",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub armor_set_rating_exponent: Option<f32>,
+    #[serde(
+        rename = "Armor Mitigation Exponent",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub armor_mitigation_exponent: Option<f32>,
+    #[serde(
+        rename = "Armor Rating Decimal Accuracy",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub armor_rating_decimal_accuracy: Option<i32>,
+    #[serde(
+        rename = "Base Damage Compound Increase",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub base_damage_compound_increase: Option<f32>,
+    #[serde(
+        rename = "Base Damage Gear Score Interval",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub base_damage_gear_score_interval: Option<u32>,
+    #[serde(
+        rename = "Min Possible Weapon Gear Score",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub min_possible_weapon_gear_score: Option<u32>,
+    #[serde(
+        rename = "Round Gearscore Up?",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub round_gearscore_up: Option<bool>,
+    #[serde(
+        rename = "Gear Score Rounding Interval",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub gear_score_rounding_interval: Option<i32>,
+    #[serde(
+        rename = "Perk Chance ItemId",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub perk_chance_item_id: Option<String>,
+    #[serde(
+        rename = "Rested Exp Percentage Per Hour",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rested_exp_percentage_per_hour: Option<f32>,
+    #[serde(
+        rename = "Rested Exp Max Percentage",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rested_exp_max_percentage: Option<f32>,
+    #[serde(
+        rename = "Rested Exp Modifier",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rested_exp_modifier: Option<f32>,
+    #[serde(
+        rename = "Rested Exp Threshold Hours",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub rested_exp_threshold_hours: Option<u32>,
+    #[serde(
+        rename = "Max Points Per Attribute",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_points_per_attribute: Option<u32>,
+    #[serde(
+        rename = "Level Damage Multiplier",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub level_damage_multiplier: Option<f32>,
+    #[serde(
+        rename = "PropertyTaxRateMin",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub property_tax_rate_min: Option<f32>,
+    #[serde(
+        rename = "PropertyTaxRateMax",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub property_tax_rate_max: Option<f32>,
+    #[serde(
+        rename = "TradingTaxRateMin",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub trading_tax_rate_min: Option<f32>,
+    #[serde(
+        rename = "TradingTaxRateMax",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub trading_tax_rate_max: Option<f32>,
+    #[serde(
+        rename = "CraftingFeeRateMin",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub crafting_fee_rate_min: Option<f32>,
+    #[serde(
+        rename = "CraftingFeeRateMax",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub crafting_fee_rate_max: Option<f32>,
+    #[serde(
+        rename = "RefiningFeeRateMin",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub refining_fee_rate_min: Option<f32>,
+    #[serde(
+        rename = "RefiningFeeRateMax",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub refining_fee_rate_max: Option<f32>,
+    #[serde(
+        rename = "SetTaxOrFeeCoolDownInMin",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub set_tax_or_fee_cool_down_in_min: Option<u32>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Database for PlayerBaseAttributes {
+    const PATH: &'static str = "sharedassets/genericassets/playerbaseattributes.pbadb";
+    type Entry = PlayerAttributeData;
+
+    fn entries(&self) -> &[PlayerAttributeData] {
+        std::slice::from_ref(&self.attributes)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RangedAttackDatabase {
+    #[serde(
+        rename = "m_rangedAttackProfiles",
+        alias = "m_rangedAttackData",
+        default
+    )]
+    pub profiles: Vec<RangedAttackData>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RangedAttackData {
+    #[serde(
+        rename = "m_profileName",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub profile_name: Option<String>,
+    #[serde(
+        rename = "m_projectileRadius",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub projectile_radius: Option<f32>,
+    #[serde(
+        rename = "m_projectileLifetimeScale",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub projectile_lifetime_scale: Option<f32>,
+    #[serde(
+        rename = "m_projectileHitScanRange",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub projectile_hit_scan_range: Option<f32>,
+    #[serde(
+        rename = "m_projectilePenetrationDepth",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub projectile_penetration_depth: Option<f32>,
+    #[serde(
+        rename = "m_projectileDamageFalloffDist",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub projectile_damage_falloff_dist: Option<f32>,
+    #[serde(
+        rename = "m_projectileDamageMaxFalloff",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub projectile_damage_max_falloff: Option<f32>,
+    #[serde(
+        rename = "m_distForProjectileDamageFalloffMax",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub dist_for_projectile_damage_falloff_max: Option<f32>,
+    #[serde(
+        rename = "m_destroyProjectileOnBlock",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub destroy_projectile_on_block: Option<bool>,
+    #[serde(
+        rename = "m_destroyProjectileOnUnsuccesfulHit",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub destroy_projectile_on_unsuccessful_hit: Option<bool>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Database for RangedAttackDatabase {
+    const PATH: &'static str = "sharedassets/genericassets/rangedattackdatabase.radb";
+    type Entry = RangedAttackData;
+
+    fn entries(&self) -> &[RangedAttackData] {
+        &self.profiles
+    }
+}
+
+/// Reads a database from the paks, with field names resolved through the file system hashes.
+pub fn load<D: Database>(fs: &'static FileSystem) -> io::Result<D> {
+    let data = fs.open(D::PATH)?;
+    Ok(object_stream::from_slice(&data, Some(&fs.hashes))?)
+}
+
+/// The entries of a database as a [`Table`].
+pub fn table<D: Database>(fs: &'static FileSystem) -> io::Result<Table> {
+    Ok(Table::new(load::<D>(fs)?.entries())?)
+}
+
+/// Every known database by file stem, as tables.
+pub fn tables(fs: &'static FileSystem) -> Vec<(&'static str, io::Result<Table>)> {
+    fn stem(path: &'static str) -> &'static str {
+        Path::new(path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or(path)
+    }
+    vec![
+        (
+            stem(FuelCategoryDatabase::PATH),
+            table::<FuelCategoryDatabase>(fs),
+        ),
+        (
+            stem(PlayerBaseAttributes::PATH),
+            table::<PlayerBaseAttributes>(fs),
+        ),
+        (
+            stem(RangedAttackDatabase::PATH),
+            table::<RangedAttackDatabase>(fs),
+        ),
+    ]
+}
+
+/// Entries flattened to one row each. Nested objects become `parent/child` columns, arrays
+/// stay a single cell.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn new<T: Serialize>(entries: &[T]) -> serde_json::Result<Self> {
+        let mut table = Table::default();
+        let mut index = HashMap::new();
+        for entry in entries {
+            let mut cells = vec![];
+            flatten("", serde_json::to_value(entry)?, &mut cells);
+            let mut row = vec![Value::Null; table.columns.len()];
+            for (column, value) in cells {
+                let i = *index.entry(column.clone()).or_insert_with(|| {
+                    table.columns.push(column);
+                    table.columns.len() - 1
+                });
+                if i >= row.len() {
+                    row.resize(i + 1, Value::Null);
+                }
+                row[i] = value;
+            }
+            table.rows.push(row);
+        }
+        let width = table.columns.len();
+        table
+            .rows
+            .iter_mut()
+            .for_each(|row| row.resize(width, Value::Null));
+        Ok(table)
+    }
+
+    /// An array with one flat object per row, leaving out empty cells.
+    pub fn to_json(&self) -> Value {
+        self.rows
+            .iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .zip(row)
+                    .filter(|(_, value)| !value.is_null())
+                    .map(|(column, value)| (column.clone(), value.clone()))
+                    .collect::<Map<_, _>>()
+                    .into()
+            })
+            .collect::<Vec<Value>>()
+            .into()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let line = |cells: Vec<String>| cells.join(",") + "\n";
+        let mut csv = line(self.columns.iter().map(|c| quote(c)).collect());
+        for row in &self.rows {
+            csv.push_str(&line(
+                row.iter()
+                    .map(|value| match value {
+                        Value::Null => String::new(),
+                        Value::String(s) => quote(s),
+                        value => quote(&value.to_string()),
+                    })
+                    .collect(),
+            ));
+        }
+        csv
+    }
+}
+
+fn flatten(prefix: &str, value: Value, cells: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let column = match prefix.is_empty() {
+                    true => key,
+                    false => format!("{prefix}/{key}"),
+                };
+                flatten(&column, value, cells);
+            }
+        }
+        value => cells.push((prefix.to_owned(), value)),
+    }
+}
+
+fn quote(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use utils::lumberyard::LumberyardSource;
+
+    #[test]
+    fn ranged_attack_table() {
+        let profile = |name: &str, radius: f32, extra: Value| {
+            let mut objects = vec![
+                json!({"field": "m_profileName", "typeId": "{03AAAB3F-5C47-5A66-9EBC-D5FA4DB353C9}", "typeName": "AZStd::string", "value": name}),
+                json!({"field": "m_projectileRadius", "typeId": "{EA2C3E90-AFBE-44D4-A90D-FAAF79BAF93D}", "typeName": "float", "value": radius}),
+            ];
+            objects.push(extra);
+            json!({
+                "field": "element",
+                "typeId": "{EC92AE9F-6D78-4592-8320-C46A6E671B5D}",
+                "typeName": "RangedAttackData",
+                "Objects": objects,
+            })
+        };
+        let stream = json!({
+            "name": "ObjectStream",
+            "version": 3,
+            "Objects": [{
+                "typeId": "{7B7FA731-D537-40DE-B9AB-FC9C3E8A2827}",
+                "typeName": "RangedAttackDatabase",
+                "Objects": [{
+                    "field": "m_rangedAttackProfiles",
+                    "typeId": "{A60E3E61-1FF6-4982-B6B8-9E4350C4C679}",
+                    "typeName": "AZStd::vector",
+                    "Objects": [
+                        profile("Bow", 0.5, json!({"field": "m_destroyProjectileOnBlock", "typeId": "{A0CA880C-AFE4-43CB-926C-59AC48496112}", "typeName": "bool", "value": true})),
+                        profile("Musket, heavy", 0.25, json!({"field": "m_speed", "typeId": "{EA2C3E90-AFBE-44D4-A90D-FAAF79BAF93D}", "typeName": "float", "value": 80.0})),
+                    ],
+                }],
+            }],
+        });
+        let data = serde_json::to_vec(&stream).unwrap();
+        let database: RangedAttackDatabase = object_stream::from_slice(&data, None).unwrap();
+        assert_eq!(database.profiles.len(), 2);
+        assert_eq!(database.profiles[0].profile_name.as_deref(), Some("Bow"));
+        assert_eq!(database.profiles[0].destroy_projectile_on_block, Some(true));
+        assert_eq!(database.profiles[1].extra["m_speed"], json!(80.0));
+
+        let table = Table::new(database.entries()).unwrap();
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(
+            table.to_json()[1],
+            json!({"m_profileName": "Musket, heavy", "m_projectileRadius": 0.25, "m_speed": 80.0})
+        );
+        let csv = table.to_csv();
+        let mut lines = csv.lines();
+        let header = lines.next().unwrap();
+        assert!(header.starts_with("m_profileName,m_projectileRadius,"));
+        assert!(lines.nth(1).unwrap().starts_with("\"Musket, heavy\",0.25,"));
+    }
+
+    #[test]
+    fn player_base_attributes() {
+        let hashes = LumberyardSource {
+            uuids: serde_json::from_str(include_str!("../../../uuids.json")).unwrap(),
+            crcs: serde_json::from_str(include_str!("../../../crcs.json")).unwrap(),
+        };
+        let hashes: &'static LumberyardSource = Box::leak(Box::new(hashes));
+        let data = include_bytes!("../../../file-system/resources/playerbaseattributes.pbadb");
+        let database: PlayerBaseAttributes = object_stream::from_slice(data, Some(hashes)).unwrap();
+
+        let attributes = &database.attributes;
+        assert_eq!(attributes.max_points_per_attribute, Some(60));
+        assert_eq!(attributes.round_gearscore_up, Some(true));
+        assert_eq!(attributes.azoth_currency.as_deref(), Some("Azoth_Currency"));
+        assert!(attributes.extra.contains_key("Item Rarity Data"));
+        assert!(!attributes.extra.contains_key("Max Points Per Attribute"));
+
+        let table = Table::new(database.entries()).unwrap();
+        assert_eq!(table.rows.len(), 1);
+    }
+}
@@ -3,6 +3,8 @@ pub mod assetcatalog;
 mod assetmanager;
 mod assetregistry;
 mod common;
+pub mod genericassets;
+pub mod slice;
 
 #[cfg(test)]
 mod test {
@@ -0,0 +1,46 @@
+//! Slices flattened with the slices they instance, loaded through the asset catalog.
+
+use crate::{assetcatalog::AssetCatalog, common::AssetId};
+use file_system::FileSystem;
+use object_stream::{
+    from_reader,
+    slice::{FlatEntity, SliceResolver},
+    ObjectStream,
+};
+use std::{io, path::Path};
+use utils::types::AssetRef;
+
+/// Loads a referenced asset by its id, falling back to the path in its hint.
+pub fn load(
+    catalog: &'static AssetCatalog,
+    fs: &'static FileSystem,
+    asset: &AssetRef,
+) -> io::Result<ObjectStream> {
+    let id = AssetId {
+        guid: asset.guid,
+        sub_id: asset.sub_id,
+    };
+    let data = match catalog.get_asset_info_by_id(id) {
+        Ok(info) => fs
+            .open(&info.relative_path)
+            .or_else(|_| fs.open(&asset.hint))?,
+        Err(_) => fs.open(&asset.hint)?,
+    };
+    Ok(from_reader(&mut data.as_slice(), Some(&fs.hashes))?)
+}
+
+/// The entities of the slice at `path` and of every slice instance in it, and what couldn't
+/// be resolved.
+pub fn flatten(
+    catalog: &'static AssetCatalog,
+    fs: &'static FileSystem,
+    path: &Path,
+) -> io::Result<(Vec<FlatEntity>, Vec<String>)> {
+    let data = fs.open(path)?;
+    let stream = from_reader(&mut data.as_slice(), Some(&fs.hashes))?;
+    let mut resolver = SliceResolver::new(Some(&fs.hashes), |asset: &AssetRef| {
+        load(catalog, fs, asset)
+    });
+    let entities = resolver.flatten(&stream);
+    Ok((entities, resolver.errors))
+}
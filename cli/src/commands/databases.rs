@@ -0,0 +1,13 @@
+use clap::Parser;
+
+use crate::common::{input::Input, output::Output};
+
+/// Exports the genericassets databases (fuel categories, player base attributes, ranged
+/// attacks) as flat `{name}.json` and `{name}.csv` tables.
+#[derive(Debug, Parser)]
+pub struct Databases {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub output: Output,
+}
@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::common::input::Input;
+
+/// Compares two ObjectStream files element by element. With `--input`, field and type names
+/// are resolved from the game's strings.
+#[derive(Debug, Parser)]
+pub struct Diff {
+    #[command(flatten)]
+    pub input: Input,
+    pub old: PathBuf,
+    pub new: PathBuf,
+    /// Print one JSON object per change
+    #[arg(long)]
+    pub json: bool,
+}
@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::common::input::Input;
+
+/// Sets or removes values of a binary ObjectStream file by query expression and writes it
+/// back. With `--input`, type names can be used in expressions.
+///
+/// Every `--set` is applied before any `--remove`, each in the order given, whatever the
+/// order of the arguments.
+#[derive(Debug, Parser)]
+pub struct Edit {
+    #[command(flatten)]
+    pub input: Input,
+    pub file: PathBuf,
+    /// Set the elements matched by EXPR to VALUE, in the XML text form. Applied before --remove
+    #[arg(long, num_args = 2, value_names = ["EXPR", "VALUE"])]
+    pub set: Vec<String>,
+    /// Remove the elements matched by EXPR, after every --set
+    #[arg(long, value_name = "EXPR")]
+    pub remove: Vec<String>,
+    /// Where to write the edited stream, defaults to overwriting the file
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
@@ -12,9 +12,20 @@ use crate::{
         CommonConfig,
     },
     traits::IArgs,
-    BYTES, CSV, MINI, PRETTY, SQL, XML, YAML,
+    BYTES, CSV, DATA, MINI, O3DE, PRETTY, SQL, XML, YAML,
 };
 
+/// ObjectStream formats offered by the picker, the first is the default.
+const OBJECTSTREAM_FORMATS: [(&str, &str, &str); 7] = [
+    (BYTES, "Binary", "default"),
+    (XML, "XML", ""),
+    (PRETTY, "JSON Pretty", ""),
+    (MINI, "JSON Minified", ""),
+    (DATA, "JSON Data", "field values only"),
+    (YAML, "YAML", ""),
+    (O3DE, "O3DE JSON", "slices as prefabs"),
+];
+
 #[derive(Debug, Parser)]
 pub struct Extract {
     #[command(flatten)]
@@ -89,7 +100,15 @@ impl<'a> IArgs<'a> for Extract {
                     (
                         "objectstream",
                         "ObjectStream",
-                        &format!("{} = default | {} | {} | {}", BYTES, XML, MINI, PRETTY),
+                        &OBJECTSTREAM_FORMATS
+                            .iter()
+                            .enumerate()
+                            .map(|(i, (value, _, _))| match i {
+                                0 => format!("{value} = default"),
+                                _ => value.to_string(),
+                            })
+                            .collect::<Vec<_>>()
+                            .join(" | "),
                     ),
                 ])
                 .interact()?;
@@ -111,12 +130,7 @@ impl<'a> IArgs<'a> for Extract {
                 .unwrap();
                 if options.contains(&"objectstream") {
                     let obj_stream = cliclack::Select::new("ObjectStream Format")
-                        .items(&[
-                            (BYTES, "Binary", "default"),
-                            (XML, "XML", ""),
-                            (PRETTY, "JSON Pretty", ""),
-                            (MINI, "JSON Minified", ""),
-                        ])
+                        .items(&OBJECTSTREAM_FORMATS)
                         .initial_value("bytes")
                         .interact()?;
 
@@ -124,6 +138,9 @@ impl<'a> IArgs<'a> for Extract {
                         XML => ObjectStreamFormat::XML,
                         MINI => ObjectStreamFormat::MINI,
                         PRETTY => ObjectStreamFormat::PRETTY,
+                        DATA => ObjectStreamFormat::DATA,
+                        YAML => ObjectStreamFormat::YAML,
+                        O3DE => ObjectStreamFormat::O3DE,
                         _ => ObjectStreamFormat::BYTES,
                     };
                 }
@@ -1,12 +1,42 @@
 use clap::Subcommand;
+use databases::Databases;
+use diff::Diff;
+use edit::Edit;
 use extract::Extract;
+use pack::Pack;
+use query::Query;
+use schema::Schema;
+use scriptcanvas::ScriptCanvas;
+use slice::Slice;
 use test::Test;
+use usages::Usages;
+use validate::Validate;
 
+pub mod databases;
+pub mod diff;
+pub mod edit;
 pub mod extract;
+pub mod pack;
+pub mod query;
+pub mod schema;
+pub mod scriptcanvas;
+pub mod slice;
 pub mod test;
+pub mod usages;
+pub mod validate;
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     Extract(Extract),
     Test(Test),
+    Pack(Pack),
+    Query(Query),
+    Schema(Schema),
+    Slice(Slice),
+    Diff(Diff),
+    Edit(Edit),
+    Databases(Databases),
+    Validate(Validate),
+    ScriptCanvas(ScriptCanvas),
+    Usages(Usages),
 }
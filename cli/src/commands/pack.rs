@@ -0,0 +1,14 @@
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+pub struct Pack {
+    /// Directory to pack. Entry names are relative to it, like the extract output is relative to ./assets
+    pub input: PathBuf,
+    /// Pak file to write
+    #[arg(short, long)]
+    pub output: PathBuf,
+    /// Convert JSON/XML ObjectStreams and JSON/CSV datasheets (with their .meta.json) back to binary
+    #[arg(long)]
+    pub convert: bool,
+}
@@ -0,0 +1,16 @@
+use clap::Parser;
+
+use crate::common::{filter::Filter, input::Input};
+
+#[derive(Debug, Parser)]
+pub struct Query {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    /// Path expression, e.g. "//TransformComponent//m_transform" or "//*[m_name=Tree]/m_id"
+    pub expr: String,
+    /// Print one JSON object per match instead of "file: path = value"
+    #[arg(long)]
+    pub json: bool,
+}
@@ -0,0 +1,15 @@
+use clap::Parser;
+
+use crate::common::{filter::Filter, input::Input, output::Output};
+
+/// Writes a JSON Schema per reflected class seen in the ObjectStreams, as
+/// `{TypeId}.schema.json`, and a summary.csv of all of them.
+#[derive(Debug, Parser)]
+pub struct Schema {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    #[command(flatten)]
+    pub output: Output,
+}
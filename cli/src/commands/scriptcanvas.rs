@@ -0,0 +1,25 @@
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+
+use crate::common::input::Input;
+
+/// Prints the nodes and connections of a Script Canvas graph as Graphviz DOT, Mermaid or JSON.
+#[derive(Debug, Parser)]
+pub struct ScriptCanvas {
+    #[command(flatten)]
+    pub input: Input,
+    /// Path of the graph in the paks, e.g. "scripts/ai/patrol.scriptcanvas"
+    pub path: PathBuf,
+    #[arg(long, default_value = "dot")]
+    pub format: GraphFormat,
+}
+
+#[derive(ValueEnum, Debug, Clone, Default, PartialEq, Eq)]
+pub enum GraphFormat {
+    #[default]
+    Dot,
+    Mermaid,
+    /// Nodes, connections and an adjacency list by node entity id
+    Json,
+}
@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::common::input::Input;
+
+/// Lists the entities a slice spawns, with the entities of the slices it instances.
+#[derive(Debug, Parser)]
+pub struct Slice {
+    #[command(flatten)]
+    pub input: Input,
+    /// Path of the slice in the paks, e.g. "slices/poi/camp.dynamicslice"
+    pub path: PathBuf,
+    /// Print one JSON object per entity
+    #[arg(long)]
+    pub json: bool,
+    /// Print the flattened slice as an O3DE prefab
+    #[arg(long, conflicts_with = "json")]
+    pub prefab: bool,
+}
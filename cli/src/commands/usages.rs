@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::common::{filter::Filter, input::Input};
+
+/// Indexes the component types and asset references of every slice, and lists the slices and
+/// entities that use a component or reference an asset.
+#[derive(Debug, Parser)]
+pub struct Usages {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    /// JSON file of the index, read when it exists and written after building it otherwise
+    #[arg(long)]
+    pub index: Option<PathBuf>,
+    /// Build the index again even if the index file exists
+    #[arg(long)]
+    pub rebuild: bool,
+    /// Component type id or name, e.g. "UiButtonComponent"
+    #[arg(long)]
+    pub component: Option<String>,
+    /// Asset guid or part of its path, e.g. "objects/tent.cgf"
+    #[arg(long)]
+    pub asset: Option<String>,
+    /// Print one JSON object per match
+    #[arg(long)]
+    pub json: bool,
+}
@@ -0,0 +1,17 @@
+use clap::Parser;
+
+use crate::common::{filter::Filter, input::Input};
+
+/// Checks the binary ObjectStreams for flags that disagree with their contents, values with
+/// the wrong size for their type, unknown type ids and trailing bytes, and summarizes the
+/// anomalies per file.
+#[derive(Debug, Parser)]
+pub struct Validate {
+    #[command(flatten)]
+    pub input: Input,
+    #[command(flatten)]
+    pub filter: Filter,
+    /// Print one JSON object per file with every finding instead of a summary
+    #[arg(long)]
+    pub json: bool,
+}
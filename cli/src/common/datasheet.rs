@@ -12,7 +12,9 @@ pub struct DatasheetConfig {
     #[arg(long, value_enum, default_value_t)]
     /// Save datasheet filenames as
     pub datasheet_filenames: DatasheetOutputMode,
-    #[arg(long)]
+    /// Write a `.meta.json` next to JSON and CSV datasheets so `pack --convert` can rebuild
+    /// them. Packing needs the untranslated values, so this can't be used with `--inline-locale`
+    #[arg(long, conflicts_with = "inline_locale")]
     pub with_meta: bool,
     #[arg(long, value_enum)]
     pub inline_locale: Option<Localization>,
@@ -6,6 +6,15 @@ use crate::traits::IArgs;
 pub struct ObjectStreamConfig {
     #[arg(long, default_value = "bytes")]
     pub objectstream: ObjectStreamFormat,
+    /// Write AZStd containers in JSON as arrays, objects and null, with typed values. These files can't be packed back
+    #[arg(long)]
+    pub objectstream_containers: bool,
+    /// With `--objectstream data`, write a .types.json next to each file with the type of every value by JSON pointer
+    #[arg(long)]
+    pub objectstream_types: bool,
+    /// Write unresolved.json to the output directory with the field name crcs and type ids missing from the hash dictionaries
+    #[arg(long)]
+    pub objectstream_unresolved: bool,
 }
 
 impl<'a> IArgs<'a> for ObjectStreamConfig {
@@ -22,6 +31,10 @@ pub enum ObjectStreamFormat {
     XML,
     MINI,
     PRETTY,
+    DATA,
     // CSV,
-    // YAML,
+    YAML,
+    /// Open 3D Engine JSON serialization, slices as prefabs
+    #[value(name = "o3de")]
+    O3DE,
 }
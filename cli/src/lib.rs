@@ -20,6 +20,8 @@ const CSV: &str = "csv";
 const SQL: &str = "sql";
 const BYTES: &str = "bytes";
 const YAML: &str = "yaml";
+const O3DE: &str = "o3de";
+const DATA: &str = "data";
 
 #[derive(Debug, Parser)]
 #[command(version, about, long_about = None)]
@@ -41,7 +43,20 @@ fn cli() -> io::Result<Args> {
 
     match &mut args.command {
         Commands::Extract(ext) => ext.configure(())?,
-        Commands::Test(_) => {}
+        Commands::Query(query) => query.input.configure(None)?,
+        Commands::Slice(slice) => slice.input.configure(None)?,
+        Commands::Validate(validate) => validate.input.configure(None)?,
+        Commands::ScriptCanvas(graph) => graph.input.configure(None)?,
+        Commands::Usages(usages) => usages.input.configure(None)?,
+        Commands::Schema(schema) => {
+            schema.input.configure(None)?;
+            schema.output.configure((None, "schemas"))?;
+        }
+        Commands::Databases(databases) => {
+            databases.input.configure(None)?;
+            databases.output.configure((None, "genericassets"))?;
+        }
+        Commands::Test(_) | Commands::Pack(_) | Commands::Diff(_) | Commands::Edit(_) => {}
     };
 
     Ok(args)
@@ -1,4 +1,7 @@
-use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::{
+    collections::HashMap,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+};
 
 use dashmap::DashMap;
 use indexmap::IndexMap;
@@ -10,8 +13,9 @@ const MAGIC: [u8; 4] = [0x11, 0x00, 0x00, 0x00];
 const VERSION: usize = 0x00;
 const NAME_CRC: usize = 0x04;
 const NAME_OFFSET_FROM_STRING: usize = 0x08;
-const TYPE_CRC: usize = 0x12;
-const TYPE_OFFSET_FROM_STRING: usize = 0x16;
+const TYPE_CRC: usize = 0x0C;
+const TYPE_OFFSET_FROM_STRING: usize = 0x10;
+const STRINGS_SIZE: usize = 0x18;
 const NUM_COLUMNS: usize = 0x44;
 const NUM_ROWS: usize = 0x48;
 const HEADER: usize = 0x5c;
@@ -264,40 +268,20 @@ impl<'a> Datasheet<'a> {
     }
 
     pub fn to_csv(&self) -> String {
-        let mut csv = String::new();
-
-        // Write the header row
-        for (i, header) in self.header.iter().enumerate() {
-            if i > 0 {
-                csv.push(',');
-            }
-            csv.push_str(&(header.text.clone()));
-        }
-        csv.push('\n');
-
-        // Write the data rows
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer
+            .write_record(self.header.iter().map(|header| &header.text))
+            .unwrap();
         for row in &self.rows {
-            for (i, cell) in row.iter().enumerate() {
-                if i > 0 {
-                    csv.push(',');
-                }
-                match cell {
-                    DatasheetCell::String(value) => {
-                        csv.push_str(&self.parse_localization(value.into()));
-                    }
-                    DatasheetCell::Number(value) => {
-                        if value.fract() == 0.0 {
-                            csv.push_str(&(*value as i64).to_string());
-                        } else {
-                            csv.push_str(&value.to_string());
-                        }
-                    }
-                    DatasheetCell::Boolean(value) => csv.push_str(&value.to_string()),
-                }
-            }
-            csv.push('\n');
+            let cells = row.iter().map(|cell| match cell {
+                DatasheetCell::String(value) => self.parse_localization(value.into()),
+                DatasheetCell::Number(value) if value.fract() == 0.0 => (*value as i64).to_string(),
+                DatasheetCell::Number(value) => value.to_string(),
+                DatasheetCell::Boolean(value) => value.to_string(),
+            });
+            writer.write_record(cells).unwrap();
         }
-        csv
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
     }
 
     pub fn to_yaml(&self) -> String {
@@ -333,6 +317,200 @@ impl<'a> Datasheet<'a> {
     }
 }
 
+impl<'a> Datasheet<'a> {
+    /// Builds a datasheet from the `.meta.json` sidecar written by `--with-meta` and the rows of
+    /// the JSON output.
+    pub fn from_json(meta: &Value, json: &Value) -> io::Result<Self> {
+        let header = header_from_meta(meta)?;
+        let rows = json
+            .as_array()
+            .ok_or_else(|| invalid_data("Datasheet JSON should be an array of rows"))?
+            .iter()
+            .map(|row| {
+                header
+                    .iter()
+                    .map(|cell| {
+                        let value = row.get(&cell.text).unwrap_or(&Value::Null);
+                        match (cell._type, value) {
+                            (1, Value::String(v)) => Ok(DatasheetCell::String(v.to_owned())),
+                            (1, Value::Null) => Ok(DatasheetCell::String(String::new())),
+                            (1, v) => Ok(DatasheetCell::String(v.to_string())),
+                            (2, Value::Number(v)) => {
+                                Ok(DatasheetCell::Number(v.as_f64().unwrap_or_default()))
+                            }
+                            (3, Value::Bool(v)) => Ok(DatasheetCell::Boolean(*v)),
+                            (_, v) => Err(invalid_data(format!(
+                                "Column {} does not accept {}",
+                                cell.text, v
+                            ))),
+                        }
+                    })
+                    .collect::<io::Result<DatasheetRow>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(Self::with_rows(meta, header, rows))
+    }
+
+    /// Builds a datasheet from the `.meta.json` sidecar and the CSV output.
+    pub fn from_csv(meta: &Value, csv: &str) -> io::Result<Self> {
+        let header = header_from_meta(meta)?;
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let columns = reader.headers().map_err(io::Error::from)?;
+
+        if columns.len() != header.len() || columns.iter().zip(&header).any(|(c, h)| c != h.text) {
+            return Err(invalid_data(
+                "Datasheet CSV header does not match the meta fields",
+            ));
+        }
+
+        let rows = reader
+            .records()
+            .map(|record| {
+                let cells = record.map_err(io::Error::from)?;
+                if cells.len() != header.len() {
+                    return Err(invalid_data(format!(
+                        "Expected {} cells, found {}",
+                        header.len(),
+                        cells.len()
+                    )));
+                }
+                cells
+                    .iter()
+                    .zip(&header)
+                    .map(|(value, cell)| match cell._type {
+                        1 => Ok(DatasheetCell::String(value.to_owned())),
+                        2 => value
+                            .parse()
+                            .map(DatasheetCell::Number)
+                            .map_err(|e| invalid_data(format!("{}: {}", cell.text, e))),
+                        3 => value
+                            .parse()
+                            .map(DatasheetCell::Boolean)
+                            .map_err(|e| invalid_data(format!("{}: {}", cell.text, e))),
+                        _ => Err(invalid_data("Unknown cell type")),
+                    })
+                    .collect::<io::Result<DatasheetRow>>()
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(Self::with_rows(meta, header, rows))
+    }
+
+    fn with_rows(meta: &Value, header: Vec<HeaderCell>, rows: Vec<DatasheetRow>) -> Self {
+        Self {
+            version: u32::from_le_bytes(MAGIC),
+            name: meta["name"].as_str().unwrap_or_default().to_owned(),
+            _type: meta["type"].as_str().unwrap_or_default().to_owned(),
+            column_count: header.len(),
+            row_count: rows.len(),
+            header,
+            rows,
+            localization: None,
+        }
+    }
+
+    /// Writes the datasheet in the binary layout read by [`Datasheet::try_from`].
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut strings = Strings::default();
+        let name = strings.insert(&self.name);
+        let _type = strings.insert(&self._type);
+
+        let mut data = Vec::with_capacity(
+            HEADER_BYTE_SIZE * self.column_count
+                + CELL_BYTE_SIZE * self.column_count * self.row_count,
+        );
+        for cell in &self.header {
+            data.extend_from_slice(&crc32(&cell.text).to_le_bytes());
+            data.extend_from_slice(&strings.insert(&cell.text).to_le_bytes());
+            data.extend_from_slice(&cell._type.to_le_bytes());
+        }
+        for row in &self.rows {
+            for cell in row {
+                let (crc, value) = match cell {
+                    DatasheetCell::String(v) => (crc32(v), strings.insert(v).to_le_bytes()),
+                    DatasheetCell::Number(v) => (crc32(&v.to_string()), (*v as f32).to_le_bytes()),
+                    DatasheetCell::Boolean(v) => (crc32(&v.to_string()), (*v as i32).to_le_bytes()),
+                };
+                data.extend_from_slice(&crc.to_le_bytes());
+                data.extend_from_slice(&value);
+            }
+        }
+
+        let strings_offset = HEADER + data.len();
+        let mut header = [0u8; HEADER];
+        header[VERSION..VERSION + 4].copy_from_slice(&self.version.to_le_bytes());
+        header[NAME_CRC..NAME_CRC + 4].copy_from_slice(&crc32(&self.name).to_le_bytes());
+        header[NAME_OFFSET_FROM_STRING..NAME_OFFSET_FROM_STRING + 4]
+            .copy_from_slice(&name.to_le_bytes());
+        header[TYPE_CRC..TYPE_CRC + 4].copy_from_slice(&crc32(&self._type).to_le_bytes());
+        header[TYPE_OFFSET_FROM_STRING..TYPE_OFFSET_FROM_STRING + 4]
+            .copy_from_slice(&_type.to_le_bytes());
+        header[STRINGS_SIZE..STRINGS_SIZE + 8]
+            .copy_from_slice(&(strings.buf.len() as u64).to_le_bytes());
+        header[DATA_END..DATA_END + 4]
+            .copy_from_slice(&((strings_offset - DATA_END - 4) as u32).to_le_bytes());
+        header[NUM_COLUMNS..NUM_COLUMNS + 4]
+            .copy_from_slice(&(self.column_count as u32).to_le_bytes());
+        header[NUM_ROWS..NUM_ROWS + 4].copy_from_slice(&(self.row_count as u32).to_le_bytes());
+
+        writer.write_all(&header)?;
+        writer.write_all(&data)?;
+        writer.write_all(&strings.buf)
+    }
+}
+
+fn header_from_meta(meta: &Value) -> io::Result<Vec<HeaderCell>> {
+    meta["fields"]
+        .as_object()
+        .ok_or_else(|| invalid_data("Datasheet meta is missing fields"))?
+        .iter()
+        .map(|(text, _type)| {
+            let _type = match _type.as_str() {
+                Some("string") => 1,
+                Some("number") => 2,
+                Some("boolean") => 3,
+                _ => return Err(invalid_data(format!("Unknown field type for {}", text))),
+            };
+            Ok(HeaderCell {
+                text: text.to_owned(),
+                _type,
+            })
+        })
+        .collect()
+}
+
+/// Null terminated string table, deduplicated the same way the game shares offsets.
+#[derive(Default)]
+struct Strings {
+    buf: Vec<u8>,
+    offsets: HashMap<String, u32>,
+}
+
+impl Strings {
+    fn insert(&mut self, string: &str) -> u32 {
+        if let Some(offset) = self.offsets.get(string) {
+            return *offset;
+        }
+        let offset = self.buf.len() as u32;
+        self.buf.extend_from_slice(string.as_bytes());
+        self.buf.push(0);
+        self.offsets.insert(string.to_owned(), offset);
+        offset
+    }
+}
+
+fn crc32(string: &str) -> u32 {
+    crc32fast::hash(string.to_lowercase().as_bytes())
+}
+
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
 impl<'a> TryFrom<Vec<u8>> for Datasheet<'a> {
     fn try_from(value: Vec<u8>) -> io::Result<Self> {
         let mut data = Cursor::new(value);
@@ -596,3 +774,38 @@ fn read_string<R: Read + Seek>(data: &mut R) -> io::Result<String> {
     }
     String::from_utf8(string).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() -> io::Result<()> {
+        let meta = json!({
+            "type": "ItemDefinitionMasterData",
+            "name": "MasterItemDefinitions_Test",
+            "fields": { "ItemID": "string", "Tier": "number", "CanBeSalvaged": "boolean" },
+        });
+        let rows = json!([
+            { "ItemID": "1hSwordT1", "Tier": 1, "CanBeSalvaged": true },
+            { "ItemID": "1hSwordT2", "Tier": 2.5, "CanBeSalvaged": false },
+            { "ItemID": "a, b \"quoted\"", "Tier": 3, "CanBeSalvaged": false },
+        ]);
+
+        let datasheet = Datasheet::from_json(&meta, &rows)?;
+        let mut buf = vec![];
+        datasheet.to_writer(&mut buf)?;
+
+        let parsed = Datasheet::try_from(buf)?;
+        assert_eq!(parsed.name, "MasterItemDefinitions_Test");
+        assert_eq!(parsed._type, "ItemDefinitionMasterData");
+        assert_eq!(parsed.to_json(), rows);
+        assert_eq!(parsed.meta()["fields"], meta["fields"]);
+
+        let text = parsed.to_csv();
+        assert!(text.contains("\"a, b \"\"quoted\"\"\",3,false\n"), "{text}");
+        let csv = Datasheet::from_csv(&meta, &text)?;
+        assert_eq!(csv.to_json(), rows);
+        Ok(())
+    }
+}
@@ -15,7 +15,9 @@ use datasheet::Datasheet;
 use flate2::Decompress;
 use image_dds::ImageFormat;
 use luac_parser::*;
-use object_stream::{from_reader, JSONObjectStream, XMLObjectStream};
+use object_stream::{
+    from_reader, unresolved::Unresolved, JSONObjectStream, StreamTag, XMLObjectStream,
+};
 use quick_xml::se::Serializer;
 use rayon::prelude::*;
 use serde::Serialize;
@@ -121,33 +123,35 @@ impl<'a, 'b> Decompressor<'a, 'b> {
 
     pub fn compressed_size(&mut self) {}
 
+    /// Outside of `extract` every type is left as is, so `FileSystem::open` returns the raw
+    /// (decompressed) bytes.
     pub fn file_type(&self) -> io::Result<FileType> {
         let _type = match (self.buf.as_slice(), self.zip.name()) {
             ([0x04, 0x00, 0x1B, 0x4C, 0x75, ..], _) => match &ARGS.command {
                 Commands::Extract(cmd) => FileType::Luac(cmd.luac),
-                _ => unreachable!(),
+                _ => FileType::Luac(false),
             },
-            ([0x00, 0x00, 0x00, 0x00, 0x03, ..], _) => match &ARGS.command {
+            (buf, _) if StreamTag::detect(buf).is_some() => match &ARGS.command {
                 Commands::Extract(extract) => {
                     FileType::ObjectStream(&extract.objectstream.objectstream)
                 }
-                _ => unreachable!(),
+                _ => FileType::ObjectStream(&ObjectStreamFormat::BYTES),
             },
             ([0x11, 0x00, 0x00, 0x00, ..], _) => match &ARGS.command {
                 Commands::Extract(extract) => FileType::Datasheet(&extract.datasheet.datasheet),
-                _ => unreachable!(),
+                _ => FileType::Datasheet(&DatasheetFormat::BYTES),
             },
             (_, n) if n.ends_with(".distribution") => match &ARGS.command {
                 Commands::Extract(cmd) => FileType::Distribution(&cmd.distribution.distribution),
-                _ => unreachable!(),
+                _ => FileType::Distribution(&DistributionFormat::BYTES),
             },
             (_, n) if n.ends_with(".vshapec") => match &ARGS.command {
                 Commands::Extract(cmd) => FileType::VShapeC(&cmd.vshapec.vshapec),
-                _ => unreachable!(),
+                _ => FileType::VShapeC(&VShapeFormat::BYTES),
             },
             (_, n) if n.ends_with(".dds") => match &ARGS.command {
                 Commands::Extract(cmd) => FileType::DDS(&cmd.dds.dds),
-                _ => unreachable!(),
+                _ => FileType::DDS(&DDSFormat::BYTES),
             },
             _ => FileType::default(),
         };
@@ -376,6 +380,18 @@ impl<'a, 'b> Decompressor<'a, 'b> {
                 _ => std::io::copy(&mut self.buf.as_slice(), writer),
             },
             FileType::ObjectStream(fmt) => {
+                if let (Commands::Extract(cmd), Some(fs)) = (&ARGS.command, FILESYSTEM.get()) {
+                    if cmd.objectstream.objectstream_unresolved {
+                        // text streams have no hashes to resolve
+                        let mut unresolved = Unresolved::new();
+                        if unresolved
+                            .scan(self.zip.name(), &self.buf, &fs.hashes)
+                            .is_ok()
+                        {
+                            fs.unresolved.lock().unwrap().merge(unresolved);
+                        }
+                    }
+                }
                 // early return no serialziation
                 if **fmt == ObjectStreamFormat::BYTES {
                     std::io::copy(&mut self.buf.as_slice(), writer)?;
@@ -386,6 +402,14 @@ impl<'a, 'b> Decompressor<'a, 'b> {
                     std::io::copy(&mut self.buf.as_slice(), writer)?;
                     return Ok(None);
                 };
+                let containers = match &ARGS.command {
+                    Commands::Extract(cmd) => cmd.objectstream.objectstream_containers,
+                    _ => false,
+                };
+                let to_json = |obj_stream| match containers {
+                    true => JSONObjectStream::with_containers(obj_stream),
+                    false => JSONObjectStream::from(obj_stream),
+                };
                 match fmt {
                     ObjectStreamFormat::XML => {
                         let obj_stream = XMLObjectStream::from(obj_stream);
@@ -396,17 +420,54 @@ impl<'a, 'b> Decompressor<'a, 'b> {
                         std::io::copy(&mut buf.as_bytes(), writer)
                     }
                     ObjectStreamFormat::MINI => {
-                        let obj_stream = JSONObjectStream::from(obj_stream);
+                        let obj_stream = to_json(obj_stream);
                         let string = serde_json::to_string(&obj_stream)
                             .expect("couldnt parse object stream to json");
                         std::io::copy(&mut string.as_bytes(), writer)
                     }
                     ObjectStreamFormat::PRETTY => {
-                        let obj_stream = JSONObjectStream::from(obj_stream);
+                        let obj_stream = to_json(obj_stream);
                         let string = serde_json::to_string_pretty(&obj_stream)
                             .expect("couldnt parse object stream to json");
                         std::io::copy(&mut string.as_bytes(), writer)
                     }
+                    ObjectStreamFormat::O3DE => {
+                        let path = std::path::Path::new(self.zip.name());
+                        let document = match path.extension().and_then(|ext| ext.to_str()) {
+                            Some("slice" | "dynamicslice") => {
+                                let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                                match obj_stream.slice() {
+                                    Some(slice) => slice.to_prefab(&name),
+                                    None => object_stream::o3de::prefab(&name, &[]),
+                                }
+                            }
+                            _ => obj_stream.to_o3de(),
+                        };
+                        let string = serde_json::to_string_pretty(&document)?;
+                        std::io::copy(&mut string.as_bytes(), writer)
+                    }
+                    ObjectStreamFormat::YAML => {
+                        let obj_stream = to_json(obj_stream);
+                        let string = serde_yml::to_string(&obj_stream)
+                            .expect("couldnt parse object stream to yaml");
+                        std::io::copy(&mut string.as_bytes(), writer)
+                    }
+                    ObjectStreamFormat::DATA => {
+                        let with_types = match &ARGS.command {
+                            Commands::Extract(cmd) => cmd.objectstream.objectstream_types,
+                            _ => false,
+                        };
+                        let data = match with_types {
+                            true => {
+                                let (data, types) = obj_stream.to_data_with_types();
+                                extra = Some(Metadata::ObjectStreamTypes(types));
+                                data
+                            }
+                            false => obj_stream.to_data(),
+                        };
+                        let string = serde_json::to_string_pretty(&data)?;
+                        std::io::copy(&mut string.as_bytes(), writer)
+                    }
                     _ => std::io::copy(&mut self.buf.as_slice(), writer),
                 }
             }
@@ -460,4 +521,6 @@ impl<'a, 'b> Decompressor<'a, 'b> {
 
 pub enum Metadata<'a> {
     Datasheet(Datasheet<'a>),
+    /// Type map of a data only ObjectStream, by JSON pointer.
+    ObjectStreamTypes(serde_json::Value),
 }
@@ -13,6 +13,7 @@ use decompressor::{Decompressor, Metadata};
 use globset::{GlobBuilder, GlobMatcher};
 use localization::Localization;
 use memmap2::Mmap;
+use object_stream::unresolved::Unresolved;
 use pelite::pe::{Pe, PeFile};
 use pelite::FileMap;
 use rayon::{prelude::*, ThreadPoolBuilder};
@@ -36,7 +37,7 @@ use zip::read::ZipArchive;
 
 pub mod azcs;
 pub mod decompressor;
-mod pak;
+pub mod pak;
 
 pub static FILESYSTEM: OnceLock<FileSystem> = OnceLock::new();
 
@@ -47,6 +48,8 @@ pub struct FileSystem {
     out_dir: &'static PathBuf,
     path_to_pak: HashMap<PathBuf, (PathBuf, String)>,
     pub hashes: LumberyardSource,
+    /// Filled during extraction with `--objectstream-unresolved`.
+    pub unresolved: Mutex<Unresolved>,
     cancel: CancellationToken,
 }
 
@@ -67,6 +70,27 @@ impl Globs {
     }
 }
 
+/// Comma separated globs, `!` before a glob to exclude what it matches.
+fn globs(patterns: &str) -> Vec<Globs> {
+    patterns
+        .split(',')
+        .map(|pattern| {
+            let pattern = pattern.trim();
+            let glob = |pattern| {
+                GlobBuilder::new(pattern)
+                    .literal_separator(true)
+                    .build()
+                    .unwrap()
+                    .compile_matcher()
+            };
+            match pattern.strip_prefix('!') {
+                Some(pattern) => Globs::Exclude(glob(pattern)),
+                None => Globs::Include(glob(pattern)),
+            }
+        })
+        .collect()
+}
+
 impl FileSystem {
     pub async fn init(
         cwd: &'static PathBuf,
@@ -87,6 +111,7 @@ impl FileSystem {
                     out_dir,
                     path_to_pak,
                     hashes,
+                    unresolved: Mutex::new(Unresolved::new()),
                     cancel,
                 }
             })
@@ -99,29 +124,7 @@ impl FileSystem {
         &'static self,
         string: Option<&String>,
     ) -> HashMap<&'static PathBuf, &'static (PathBuf, String)> {
-        let mut matchers = vec![];
-        if let Some(patterns) = string {
-            patterns.split(',').for_each(|pattern| {
-                let pattern = pattern.trim();
-                if let Some(pattern) = pattern.strip_prefix('!') {
-                    matchers.push(Globs::Exclude(
-                        GlobBuilder::new(pattern)
-                            .literal_separator(true)
-                            .build()
-                            .unwrap()
-                            .compile_matcher(),
-                    ))
-                } else {
-                    matchers.push(Globs::Include(
-                        GlobBuilder::new(pattern)
-                            .literal_separator(true)
-                            .build()
-                            .unwrap()
-                            .compile_matcher(),
-                    ))
-                }
-            })
-        };
+        let matchers = string.map(|patterns| globs(patterns)).unwrap_or_default();
 
         self.path_to_pak
             .iter()
@@ -210,7 +213,7 @@ impl FileSystem {
                 }
                 None => None,
             },
-            Commands::Test(_) => unreachable!(),
+            _ => unreachable!(),
         };
 
         let locale = Arc::new(locale);
@@ -393,6 +396,34 @@ fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metad
                     path.set_extension(ext);
                 }
             }
+            ObjectStreamFormat::O3DE => {
+                if ext == "slice" || ext == "dynamicslice" {
+                    ext.push(".prefab");
+                } else {
+                    ext.push(".json");
+                }
+                path.set_extension(ext);
+            }
+            ObjectStreamFormat::YAML => {
+                if ext != "yaml" {
+                    ext.push(".yaml");
+                    path.set_extension(ext);
+                }
+            }
+            ObjectStreamFormat::DATA => {
+                if ext != "json" {
+                    ext.push(".json");
+                    path.set_extension(ext);
+                }
+                if let Some(Metadata::ObjectStreamTypes(types)) = meta {
+                    std::fs::create_dir_all(path.parent().unwrap())
+                        .expect("failed to create directory");
+                    let mut file =
+                        std::fs::File::create(path.with_extension("types.json")).unwrap();
+                    file.write_all(&serde_json::to_vec_pretty(types).unwrap())
+                        .unwrap();
+                }
+            }
             _ => {}
         },
         FileType::Datasheet(fmt) => {
@@ -413,11 +444,12 @@ fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metad
                                         .join(format!("{}/{}", datasheet._type, datasheet.name));
                                     path = path.with_extension(&ext);
                                 }
+                                _ => {}
                             }
                         }
                     }
                 }
-                Commands::Test(_) => unreachable!(),
+                _ => unreachable!(),
             };
             match fmt {
                 DatasheetFormat::BYTES => {}
@@ -427,14 +459,18 @@ fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metad
                         path.set_extension(ext);
                     }
                 }
-                DatasheetFormat::MINI | DatasheetFormat::PRETTY => {
-                    if ext != "json" {
-                        ext.push(".json");
+                DatasheetFormat::MINI | DatasheetFormat::PRETTY | DatasheetFormat::CSV => {
+                    let target = match fmt {
+                        DatasheetFormat::CSV => "csv",
+                        _ => "json",
+                    };
+                    if ext != target {
+                        ext.push(format!(".{target}"));
                         path.set_extension(ext);
                     }
                     let with_meta = match &ARGS.command {
                         Commands::Extract(cmd) => cmd.datasheet.with_meta,
-                        Commands::Test(_) => unreachable!(),
+                        _ => unreachable!(),
                     };
 
                     if let Some(meta) = &meta {
@@ -462,16 +498,11 @@ fn handle_extension(file_type: &FileType, mut path: PathBuf, meta: Option<&Metad
                                     .unwrap();
                                     // datasheet.to_json_simd(pretty)
                                 }
+                                _ => {}
                             }
                         };
                     }
                 }
-                DatasheetFormat::CSV => {
-                    if ext != "csv" {
-                        ext.push(".csv");
-                        path.set_extension(ext);
-                    }
-                }
                 DatasheetFormat::YAML => {
                     if ext != "yaml" {
                         ext.push(".yaml");
@@ -679,6 +710,24 @@ mod tests {
     //     Ok(())
     // }
 
+    #[test]
+    fn default_globs() {
+        let matches = |patterns, path: &str| globs(patterns).iter().any(|g| g.is_match(&path));
+        assert!(matches(
+            "**/*",
+            "sharedassets/genericassets/rangedattackdatabase.radb"
+        ));
+        assert!(matches("**/*", "objects/tent.cgf"));
+        assert!(matches(
+            "**/*.slice,**/*.dynamicslice",
+            "slices/poi/camp.dynamicslice"
+        ));
+        assert!(!matches(
+            "**/*.slice,**/*.dynamicslice",
+            "slices/poi/camp.json"
+        ));
+    }
+
     #[test]
     fn pak_map() {
         let root = "C:/Program Files (x86)/Steam/steamapps/common/New World";
@@ -1,7 +1,151 @@
-use std::fs::File;
-use zip::ZipArchive;
+use datasheet::Datasheet;
+use object_stream::{from_reader, ObjectStream, StreamTag};
+use serde_json::Value;
+use std::{
+    fs::File,
+    io::{self, Seek, Write},
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
 
 pub struct Pak {
     file: File,
     archive: ZipArchive<File>,
 }
+
+/// Writes every file under `dir` into a pak. Entry names are relative to `dir`, which should
+/// be laid out like the extract output (relative to the `assets` root), so the pak can be
+/// dropped into `assets` and resolve to the same paths `map()` produces.
+///
+/// With `convert`, sources written by extract are turned back into engine formats:
+/// `*.json`/`*.xml` ObjectStreams to binary and `*.datasheet.json`/`*.datasheet.csv` to
+/// binary datasheets using their `.meta.json` sidecar.
+///
+/// Files that can't be read or converted are left out of the pak. Returns the number of
+/// files written and an error for each file that was left out.
+pub fn pack<P, W, F>(dir: P, writer: W, convert: bool, cb: F) -> io::Result<(usize, Vec<String>)>
+where
+    P: AsRef<Path>,
+    W: Write + Seek,
+    F: Fn(&Path),
+{
+    let dir = dir.as_ref();
+    let mut zip = ZipWriter::new(writer);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut files = WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect::<Vec<_>>();
+    if convert {
+        files.retain(|path| !is_meta(path));
+    }
+
+    let mut errors = vec![];
+    for path in &files {
+        cb(path);
+        let relative = path.strip_prefix(dir).map_err(io::Error::other)?;
+        let entry = match convert {
+            true => convert_entry(path, relative),
+            false => std::fs::read(path).map(|data| (relative.to_path_buf(), data)),
+        };
+        let (name, data) = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.push(format!("{}: {}", path.display(), e));
+                continue;
+            }
+        };
+
+        let name = name
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        zip.start_file(name, options)?;
+        zip.write_all(&data)?;
+    }
+
+    zip.finish()?;
+    Ok((files.len() - errors.len(), errors))
+}
+
+fn is_meta(path: &Path) -> bool {
+    path.to_str().is_some_and(|p| p.ends_with(".meta.json"))
+}
+
+/// Reverses the extension handling done on extract (`foo.dynamicslice.json` ->
+/// `foo.dynamicslice`) and converts the contents back to bytes.
+fn convert_entry(path: &Path, relative: &Path) -> io::Result<(PathBuf, Vec<u8>)> {
+    let data = std::fs::read(path)?;
+    let original = relative.with_extension("");
+
+    let (Some(ext), Some(inner)) = (
+        relative.extension().and_then(|e| e.to_str()),
+        original.extension().and_then(|e| e.to_str()),
+    ) else {
+        return Ok((relative.to_path_buf(), data));
+    };
+
+    let converted = match (inner, ext) {
+        ("datasheet", "json") => {
+            let meta = read_meta(path)?;
+            let json: Value = serde_json::from_slice(&data)?;
+            Some(datasheet_to_bytes(Datasheet::from_json(&meta, &json)?)?)
+        }
+        ("datasheet", "csv") => {
+            let meta = read_meta(path)?;
+            let csv = std::str::from_utf8(&data)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            Some(datasheet_to_bytes(Datasheet::from_csv(&meta, csv)?)?)
+        }
+        (_, "json" | "xml")
+            if matches!(
+                StreamTag::detect(&data),
+                Some(StreamTag::XML | StreamTag::JSON)
+            ) =>
+        {
+            let stream = from_reader(&mut data.as_slice(), None)?;
+            Some(object_stream_to_bytes(stream)?)
+        }
+        _ => None,
+    };
+
+    Ok(match converted {
+        Some(bytes) => (original, bytes),
+        None => (relative.to_path_buf(), data),
+    })
+}
+
+/// `javelindata_foo.datasheet.json` -> `javelindata_foo.meta.json`, as written by `--with-meta`.
+fn read_meta(path: &Path) -> io::Result<Value> {
+    let stem = path.with_extension("");
+    let meta = stem.with_extension("meta.json");
+    let data = std::fs::read(&meta).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!(
+                "Missing datasheet meta {} (extract with --with-meta): {}",
+                meta.display(),
+                e
+            ),
+        )
+    })?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+fn datasheet_to_bytes(datasheet: Datasheet) -> io::Result<Vec<u8>> {
+    let mut buf = vec![];
+    datasheet.to_writer(&mut buf)?;
+    Ok(buf)
+}
+
+fn object_stream_to_bytes(stream: ObjectStream) -> io::Result<Vec<u8>> {
+    let mut buf = vec![];
+    stream.to_writer(&mut buf)?;
+    Ok(buf)
+}
@@ -0,0 +1,432 @@
+//! Binary streams parsed in place: element data points into the input buffer and type and
+//! field names into the hash dictionaries, nothing is copied until [`ObjectStreamRef::into_owned`].
+
+use crate::{
+    check_version, error::Error, error::Result, path_segment, query, Element, Node, ObjectStream,
+    StreamTag, BINARY_STREAM_TAG, ST_BINARYFLAG_ELEMENT_END, ST_BINARYFLAG_EXTRA_SIZE_FIELD,
+    ST_BINARYFLAG_HAS_NAME, ST_BINARYFLAG_HAS_VALUE, ST_BINARYFLAG_HAS_VERSION,
+    ST_BINARY_VALUE_SIZE_MASK,
+};
+use utils::{lumberyard::LumberyardSource, types::AzValue};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectStreamRef<'a> {
+    version: u32,
+    elements: Vec<ElementRef<'a>>,
+}
+
+/// Everything stored before an element's data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ElementHeader<'a> {
+    pub flags: u8,
+    pub name_crc: Option<u32>,
+    pub version: Option<u8>,
+    pub id: Uuid,
+    /// Only stored in version 2 streams.
+    pub specialization: Option<Uuid>,
+    /// Type name from the hash dictionary.
+    pub name: Option<&'a str>,
+    /// Field name from the hash dictionary.
+    pub field: Option<&'a str>,
+}
+
+impl ElementHeader<'_> {
+    pub(crate) fn path_segment(&self) -> String {
+        path_segment(
+            self.field,
+            self.name.unwrap_or_default(),
+            self.name_crc,
+            &self.id,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementRef<'a> {
+    header: ElementHeader<'a>,
+    data: Option<&'a [u8]>,
+    elements: Vec<ElementRef<'a>>,
+}
+
+impl<'a> ObjectStreamRef<'a> {
+    /// Parses a binary stream. Text streams have nothing to borrow, use
+    /// [`crate::from_reader`] for those.
+    pub fn parse(buf: &'a [u8], hashes: Option<&'a LumberyardSource>) -> Result<Self> {
+        let mut reader = SliceReader::new(buf, hashes)?;
+        let mut elements = vec![];
+        while let Some(element) = reader.element()? {
+            elements.push(element);
+        }
+        Ok(Self {
+            version: reader.version,
+            elements,
+        })
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn elements(&self) -> &[ElementRef<'a>] {
+        &self.elements
+    }
+
+    /// Finds every element matching the [`query`] path expression, starting at the roots.
+    pub fn select(&self, expr: &str) -> Result<Vec<query::Match<'_, ElementRef<'a>>>> {
+        Ok(query::Query::parse(expr)?.select_ref(&self.elements))
+    }
+
+    /// Copies the stream into the owned model, to edit or convert it.
+    pub fn into_owned(self) -> ObjectStream {
+        ObjectStream {
+            _tag: StreamTag::BINARY,
+            version: self.version,
+            elements: self
+                .elements
+                .into_iter()
+                .map(ElementRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+impl<'a> ElementRef<'a> {
+    pub fn header(&self) -> &ElementHeader<'a> {
+        &self.header
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.header.flags
+    }
+
+    pub fn name_crc(&self) -> Option<u32> {
+        self.header.name_crc
+    }
+
+    pub fn version(&self) -> Option<u8> {
+        self.header.version
+    }
+
+    pub fn id(&self) -> &Uuid {
+        &self.header.id
+    }
+
+    pub fn specialization(&self) -> Option<&Uuid> {
+        self.header.specialization.as_ref()
+    }
+
+    /// Type name from the hash dictionary.
+    pub fn name(&self) -> Option<&'a str> {
+        self.header.name
+    }
+
+    /// Field name from the hash dictionary.
+    pub fn field(&self) -> Option<&'a str> {
+        self.header.field
+    }
+
+    pub fn data(&self) -> Option<&'a [u8]> {
+        self.data
+    }
+
+    pub fn elements(&self) -> &[ElementRef<'a>] {
+        &self.elements
+    }
+
+    /// The decoded data, `None` for elements without data.
+    pub fn az_value(&self) -> Option<AzValue> {
+        Node::az_value(self)
+    }
+
+    /// The value as it is written in the XML format, `None` for elements without data.
+    pub fn value(&self) -> Option<String> {
+        Node::value(self)
+    }
+
+    pub fn into_owned(self) -> Element {
+        let header = self.header;
+        Element {
+            flags: header.flags,
+            name_crc: header.name_crc,
+            version: header.version,
+            id: header.id,
+            specialization: header.specialization,
+            name: header.name.unwrap_or_default().to_owned(),
+            data_size: self.data.map(<[u8]>::len),
+            data: self.data.map(<[u8]>::to_vec),
+            elements: self
+                .elements
+                .into_iter()
+                .map(ElementRef::into_owned)
+                .collect(),
+            field: header.field.map(str::to_owned),
+        }
+    }
+}
+
+impl Node for ElementRef<'_> {
+    fn id(&self) -> &Uuid {
+        &self.header.id
+    }
+
+    fn type_name(&self) -> &str {
+        self.header.name.unwrap_or_default()
+    }
+
+    fn name_crc(&self) -> Option<u32> {
+        self.header.name_crc
+    }
+
+    fn field(&self) -> Option<&str> {
+        self.header.field
+    }
+
+    fn version(&self) -> Option<u8> {
+        self.header.version
+    }
+
+    fn data(&self) -> Option<&[u8]> {
+        self.data
+    }
+
+    fn children(&self) -> &[Self] {
+        &self.elements
+    }
+}
+
+impl From<ObjectStreamRef<'_>> for ObjectStream {
+    fn from(value: ObjectStreamRef<'_>) -> Self {
+        value.into_owned()
+    }
+}
+
+impl From<ElementRef<'_>> for Element {
+    fn from(value: ElementRef<'_>) -> Self {
+        value.into_owned()
+    }
+}
+
+/// The header and data of an element whose children weren't read yet.
+pub(crate) type Opened<'a> = (ElementHeader<'a>, Option<&'a [u8]>);
+
+/// Reads a binary stream in place, keeping the headers of the open elements for errors.
+pub(crate) struct SliceReader<'a> {
+    buf: &'a [u8],
+    offset: usize,
+    flags: Option<u8>,
+    path: Vec<ElementHeader<'a>>,
+    hashes: Option<&'a LumberyardSource>,
+    pub(crate) version: u32,
+}
+
+impl<'a> SliceReader<'a> {
+    /// Reads the stream tag and version.
+    pub(crate) fn new(buf: &'a [u8], hashes: Option<&'a LumberyardSource>) -> Result<Self> {
+        let mut reader = Self {
+            buf,
+            offset: 0,
+            flags: None,
+            path: vec![],
+            hashes,
+            version: 0,
+        };
+        let tag = reader.take(1)?[0];
+        if tag != BINARY_STREAM_TAG {
+            reader.offset = 0;
+            return Err(reader.error(format!("not a binary ObjectStream, tag {tag:#04x}")));
+        }
+        reader.version = check_version(reader.u32()?)?;
+        Ok(reader)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let buf = self.buf;
+        match buf.get(self.offset..self.offset + len) {
+            Some(bytes) => {
+                self.offset += len;
+                Ok(bytes)
+            }
+            None => Err(self.error(format!("unexpected end of stream reading {len} bytes"))),
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn uuid(&mut self) -> Result<Uuid> {
+        Ok(Uuid::from_bytes(self.take(16)?.try_into().unwrap()))
+    }
+
+    pub(crate) fn error(&self, reason: impl Into<String>) -> Error {
+        Error::Parse {
+            offset: self.offset as u64,
+            flags: self.flags,
+            path: self.path.iter().map(ElementHeader::path_segment).collect(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Bytes read so far, from the start of the stream.
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Bytes left after the current position.
+    pub(crate) fn remaining(&self) -> usize {
+        self.buf.len() - self.offset
+    }
+
+    /// Number of elements currently open.
+    pub(crate) fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    /// Reads the header and data of the next element, `None` at the end marker of the parent.
+    /// The element stays open until [`SliceReader::close`], after its children were read.
+    pub(crate) fn open(&mut self) -> Result<Option<Opened<'a>>> {
+        self.flags = None;
+        let flags = self.take(1)?[0];
+        if flags == ST_BINARYFLAG_ELEMENT_END {
+            return Ok(None);
+        }
+        self.flags = Some(flags);
+
+        let name_crc = match flags & ST_BINARYFLAG_HAS_NAME > 0 {
+            true => Some(self.u32()?),
+            false => None,
+        };
+        let version = match flags & ST_BINARYFLAG_HAS_VERSION > 0 {
+            true => Some(self.take(1)?[0]),
+            false => None,
+        };
+        let id = self.uuid()?;
+        let hashes = self.hashes;
+        let mut header = ElementHeader {
+            flags,
+            name_crc,
+            version,
+            id,
+            specialization: None,
+            name: hashes.and_then(|h| h.uuids.get(&id)).map(String::as_str),
+            field: name_crc.and_then(|crc| Some(hashes?.crcs.get(&crc)?.as_str())),
+        };
+        self.path.push(header);
+
+        if self.version == 2 {
+            header.specialization = Some(self.uuid()?);
+        }
+
+        let data = match flags & ST_BINARYFLAG_HAS_VALUE > 0 {
+            true => {
+                let value_bytes = flags & ST_BINARY_VALUE_SIZE_MASK;
+                let size = match (flags & ST_BINARYFLAG_EXTRA_SIZE_FIELD > 0, value_bytes) {
+                    (false, size) => size as usize,
+                    (true, 1) => self.take(1)?[0] as usize,
+                    (true, 2) => u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as usize,
+                    (true, 4) => self.u32()? as usize,
+                    (true, _) => {
+                        return Err(self.error(format!(
+                            "unsupported extra size field of {value_bytes} bytes"
+                        )))
+                    }
+                };
+                Some(self.take(size)?)
+            }
+            false => None,
+        };
+        Ok(Some((header, data)))
+    }
+
+    pub(crate) fn close(&mut self) {
+        self.path.pop();
+    }
+
+    /// Reads the next element and its children.
+    fn element(&mut self) -> Result<Option<ElementRef<'a>>> {
+        let Some((header, data)) = self.open()? else {
+            return Ok(None);
+        };
+        let mut elements = vec![];
+        while let Some(child) = self.element()? {
+            elements.push(child);
+        }
+        self.close();
+        Ok(Some(ElementRef {
+            header,
+            data,
+            elements,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field_crc, from_reader};
+    use std::io::Cursor;
+
+    #[test]
+    fn borrowed() {
+        let mut root = Element {
+            id: Uuid::from_u128(1),
+            version: Some(2),
+            elements: vec![
+                Element {
+                    name_crc: Some(field_crc("m_value")),
+                    id: utils::types::INT,
+                    data: Some(7i32.to_be_bytes().to_vec()),
+                    ..Default::default()
+                },
+                Element {
+                    name_crc: Some(field_crc("m_name")),
+                    id: utils::types::AZSTD_STRING,
+                    data: Some(vec![b'a'; 300]),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        root.update_flags();
+        let mut buf = vec![];
+        for version in 1..=3 {
+            buf.clear();
+            ObjectStream {
+                version,
+                elements: vec![root.clone()],
+                ..Default::default()
+            }
+            .to_writer(&mut buf)
+            .unwrap();
+
+            let hashes = LumberyardSource {
+                uuids: [(Uuid::from_u128(1), "Root".to_string())].into(),
+                crcs: [(field_crc("m_value"), "m_value".to_string())].into(),
+            };
+            let stream = ObjectStreamRef::parse(&buf, Some(&hashes)).unwrap();
+            let value = &stream.elements()[0].elements()[0];
+            assert_eq!(value.field(), Some("m_value"));
+            assert_eq!(value.az_value(), Some(AzValue::I32(7)));
+            assert!(std::ptr::eq(
+                stream.elements()[0].elements()[1].data().unwrap().as_ptr(),
+                &buf[buf.len() - 303]
+            ));
+
+            let hashes: &'static LumberyardSource = Box::leak(Box::new(hashes));
+            let owned = from_reader(&mut Cursor::new(&buf), Some(hashes)).unwrap();
+            let parsed = ObjectStreamRef::parse(&buf, Some(hashes)).unwrap();
+            assert_eq!(parsed.into_owned().elements, owned.elements);
+        }
+
+        // cut in the middle of the int value
+        let truncated = &buf[..5 + 18 + 1 + 4 + 16 + 2];
+        match ObjectStreamRef::parse(truncated, None) {
+            Err(Error::Parse { offset, path, .. }) => {
+                assert_eq!(offset, 5 + 18 + 1 + 4 + 16);
+                assert_eq!(path.len(), 2);
+            }
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+}
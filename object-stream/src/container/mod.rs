@@ -0,0 +1,314 @@
+//! Recognizes the AZStd containers in element trees, so they can be written as plain JSON.
+//!
+//! Containers store their items as children named `element`, pairs as `value1` and `value2`
+//! children and optionals as at most one `value` child. Empty containers have no children to
+//! go by, those are recognized by their type id or type name.
+
+use crate::{field_crc, Element, JSONElement, JSONObjectStream, Node, ObjectStream};
+use serde_json::{Map, Value};
+use std::{collections::HashSet, sync::LazyLock};
+use utils::types::*;
+
+#[derive(Debug)]
+pub enum Container<'a, E = Element> {
+    /// Vectors, lists, sets, arrays...
+    Sequence(&'a [E]),
+    /// Maps and sequences of pairs, with the key and value of each pair.
+    Map(Vec<(&'a E, &'a E)>),
+    Optional(Option<&'a E>),
+}
+
+static ELEMENT: LazyLock<u32> = LazyLock::new(|| field_crc("element"));
+static VALUE: LazyLock<u32> = LazyLock::new(|| field_crc("value"));
+static VALUE1: LazyLock<u32> = LazyLock::new(|| field_crc("value1"));
+static VALUE2: LazyLock<u32> = LazyLock::new(|| field_crc("value2"));
+
+const SEQUENCES: [&str; 9] = [
+    "vector",
+    "list",
+    "forward_list",
+    "set",
+    "unordered_set",
+    "unordered_multiset",
+    "fixed_vector",
+    "fixed_list",
+    "array",
+];
+const MAPS: [&str; 3] = ["map", "unordered_map", "unordered_multimap"];
+
+impl Element {
+    /// The container this element is, if any.
+    pub fn container(&self) -> Option<Container<'_>> {
+        container_of(self)
+    }
+
+    /// `value1` and `value2` of an `AZStd::pair`.
+    pub(crate) fn pair(&self) -> Option<(&Element, &Element)> {
+        pair_of(self)
+    }
+}
+
+pub(crate) fn container_of<E: Node>(element: &E) -> Option<Container<'_, E>> {
+    if element.data().is_some() {
+        return None;
+    }
+    let (id, name, children) = (element.id(), element.type_name(), element.children());
+    if *id == AZSTD_OPTIONAL || is_named(name, &["optional"]) {
+        return match children {
+            [] => Some(Container::Optional(None)),
+            [value] if value.name_crc() == Some(*VALUE) => Some(Container::Optional(Some(value))),
+            _ => None,
+        };
+    }
+
+    let is_map = matches!(
+        *id,
+        AZSTD_MAP | AZSTD_UNORDERED_MAP | AZSTD_UNORDERED_MULTIMAP
+    ) || is_named(name, &MAPS);
+    let is_sequence = matches!(
+        *id,
+        AZSTD_VECTOR
+            | AZSTD_LIST
+            | AZSTD_FORWARD_LIST
+            | AZSTD_SET
+            | AZSTD_UNORDERED_SET
+            | AZSTD_UNORDERED_MULTISET
+            | AZSTD_FIXED_VECTOR
+            | AZSTD_FIXED_LIST
+            | AZSTD_ARRAY
+    ) || is_named(name, &SEQUENCES);
+
+    if children.is_empty() {
+        return match (is_map, is_sequence) {
+            (true, _) => Some(Container::Map(vec![])),
+            (_, true) => Some(Container::Sequence(&[])),
+            _ => None,
+        };
+    }
+    if !children
+        .iter()
+        .all(|child| child.name_crc() == Some(*ELEMENT))
+    {
+        return None;
+    }
+
+    let pairs = children.iter().map(pair_of).collect::<Option<Vec<_>>>();
+    match pairs {
+        Some(pairs) => Some(Container::Map(pairs)),
+        None => Some(Container::Sequence(children)),
+    }
+}
+
+fn pair_of<E: Node>(element: &E) -> Option<(&E, &E)> {
+    match element.children() {
+        [key, value] if key.name_crc() == Some(*VALUE1) && value.name_crc() == Some(*VALUE2) => {
+            Some((key, value))
+        }
+        _ => None,
+    }
+}
+
+/// `AZStd::vector`, `AZStd::vector<int>` and so on.
+fn is_named(name: &str, containers: &[&str]) -> bool {
+    let name = name.strip_prefix("AZStd::").unwrap_or(name);
+    let name = name.split('<').next().unwrap_or(name).trim();
+    containers.contains(&name)
+}
+
+impl JSONObjectStream {
+    /// Like the `From<ObjectStream>` conversion, but containers are written as JSON arrays,
+    /// objects (maps with string or number keys) and null or the value (optionals), and values
+    /// are typed JSON instead of strings. This can't be converted back to binary.
+    pub fn with_containers(value: ObjectStream) -> Self {
+        Self {
+            name: "ObjectStream".into(),
+            version: value.version,
+            elements: value
+                .elements
+                .iter()
+                .map(JSONElement::with_containers)
+                .collect(),
+        }
+    }
+}
+
+impl JSONElement {
+    fn with_containers(element: &Element) -> Self {
+        let (value, elements) = match (element.container(), &element.data) {
+            (Some(container), _) => (Some(container_json(container)), None),
+            (None, Some(data)) => (Some(AzValue::decode(&element.id, data).to_json()), None),
+            (None, None) => (
+                None,
+                Some(
+                    element
+                        .elements
+                        .iter()
+                        .map(JSONElement::with_containers)
+                        .collect(),
+                ),
+            ),
+        };
+        Self {
+            field: element.field_name(),
+            id: element.id,
+            name: element.name.clone(),
+            specialization: element.specialization,
+            value,
+            version: element.version,
+            elements,
+        }
+    }
+}
+
+fn container_json(container: Container) -> Value {
+    match container {
+        Container::Sequence(items) => Value::Array(items.iter().map(item_json).collect()),
+        Container::Optional(value) => value.map(item_json).unwrap_or(Value::Null),
+        Container::Map(pairs) => match unique_keys(&pairs) {
+            Some(keys) => Value::Object(
+                keys.into_iter()
+                    .zip(pairs.iter().map(|(_, value)| item_json(value)))
+                    .collect::<Map<_, _>>(),
+            ),
+            None => Value::Array(
+                pairs
+                    .iter()
+                    .map(|(key, value)| Value::Array(vec![item_json(key), item_json(value)]))
+                    .collect(),
+            ),
+        },
+    }
+}
+
+/// Values and containers inline, anything else as a node without the implied field name.
+fn item_json(element: &Element) -> Value {
+    if let Some(container) = element.container() {
+        return container_json(container);
+    }
+    match (&element.data, element.elements.is_empty()) {
+        (Some(data), true) => AzValue::decode(&element.id, data).to_json(),
+        _ => {
+            let mut node = JSONElement::with_containers(element);
+            node.field = None;
+            serde_json::to_value(node).unwrap_or(Value::Null)
+        }
+    }
+}
+
+/// The keys of a map as strings, if they are all unique strings or numbers.
+pub(crate) fn unique_keys<E: Node>(pairs: &[(&E, &E)]) -> Option<Vec<String>> {
+    pairs
+        .iter()
+        .map(|(key, _)| key_string(*key))
+        .collect::<Option<Vec<_>>>()
+        .filter(|keys| keys.iter().collect::<HashSet<_>>().len() == keys.len())
+}
+
+fn key_string(key: &impl Node) -> Option<String> {
+    if !key.children().is_empty() {
+        return None;
+    }
+    match key.az_value()?.to_json() {
+        Value::String(key) => Some(key),
+        Value::Number(key) => Some(key.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn int(field: &str, value: i32) -> Element {
+        element(field, INT, Some(value.to_be_bytes().to_vec()), vec![])
+    }
+
+    fn string(field: &str, value: &str) -> Element {
+        element(field, AZSTD_STRING, Some(value.as_bytes().to_vec()), vec![])
+    }
+
+    fn pair(key: &str, value: i32) -> Element {
+        element(
+            "element",
+            AZSTD_PAIR,
+            None,
+            vec![string("value1", key), int("value2", value)],
+        )
+    }
+
+    #[test]
+    fn containers() {
+        let root = element(
+            "root",
+            Uuid::from_u128(1),
+            None,
+            vec![
+                element(
+                    "m_ints",
+                    Uuid::from_u128(2),
+                    None,
+                    vec![int("element", 1), int("element", 2)],
+                ),
+                element(
+                    "m_map",
+                    Uuid::from_u128(3),
+                    None,
+                    vec![pair("a", 1), pair("b", 2)],
+                ),
+                element(
+                    "m_multimap",
+                    Uuid::from_u128(3),
+                    None,
+                    vec![pair("a", 1), pair("a", 2)],
+                ),
+                Element {
+                    name: "AZStd::vector".into(),
+                    ..element("m_empty", Uuid::from_u128(4), None, vec![])
+                },
+                Element {
+                    name: "AZStd::optional<int>".into(),
+                    ..element("m_none", Uuid::from_u128(5), None, vec![])
+                },
+                element(
+                    "m_structs",
+                    Uuid::from_u128(6),
+                    None,
+                    vec![element(
+                        "element",
+                        Uuid::from_u128(7),
+                        None,
+                        vec![int("m_x", 3)],
+                    )],
+                ),
+                element("m_struct", Uuid::from_u128(7), None, vec![int("m_x", 4)]),
+            ],
+        );
+        let stream = ObjectStream {
+            version: 3,
+            elements: vec![root],
+            ..Default::default()
+        };
+        let json = serde_json::to_value(JSONObjectStream::with_containers(stream)).unwrap();
+        let values = json["Objects"][0]["Objects"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|node| (node["field"].as_str().unwrap(), node["value"].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(values[0], ("m_ints", json!([1, 2])));
+        assert_eq!(values[1], ("m_map", json!({"a": 1, "b": 2})));
+        assert_eq!(values[2], ("m_multimap", json!([["a", 1], ["a", 2]])));
+        assert_eq!(values[3], ("m_empty", json!([])));
+        assert_eq!(values[4], ("m_none", Value::Null));
+        assert_eq!(values[5].1[0]["Objects"][0]["value"], json!(3));
+        assert!(values[5].1[0].get("field").is_none());
+        assert_eq!(values[6].1, Value::Null);
+        assert_eq!(
+            json["Objects"][0]["Objects"][6]["Objects"][0]["value"],
+            json!(4)
+        );
+    }
+}
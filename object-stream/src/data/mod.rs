@@ -0,0 +1,248 @@
+//! Data only JSON: classes are plain objects keyed by field name, values are typed JSON and
+//! containers are arrays, objects and null like [`crate::container`]. Base classes are merged
+//! into the class deriving from them.
+//!
+//! The type of every node can be written next to it as a map of JSON pointer (into the data)
+//! to `{"typeId", "typeName", "version"}`.
+
+use crate::{braced, container::Container, slice::BASE_CLASSES, Element, Node, ObjectStream};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+impl ObjectStream {
+    /// The data of the root element, or an array of them if the stream has several.
+    pub fn to_data(&self) -> Value {
+        self.data(None)
+    }
+
+    /// [`ObjectStream::to_data`] and the type map of every node in it.
+    pub fn to_data_with_types(&self) -> (Value, Value) {
+        let mut types = Map::new();
+        let data = self.data(Some(&mut types));
+        (data, Value::Object(types))
+    }
+
+    fn data(&self, mut types: Option<&mut Map<String, Value>>) -> Value {
+        match self.elements.as_slice() {
+            [root] => data(root, String::new(), &mut types),
+            roots => Value::Array(
+                roots
+                    .iter()
+                    .enumerate()
+                    .map(|(i, root)| data(root, format!("/{i}"), &mut types))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// The data of one element, as in [`ObjectStream::to_data`].
+pub(crate) fn element_data(element: &Element) -> Value {
+    data(element, String::new(), &mut None)
+}
+
+fn data(element: &Element, pointer: String, types: &mut Option<&mut Map<String, Value>>) -> Value {
+    if let Some(types) = types {
+        let mut entry = json!({
+            "typeId": braced(&element.id),
+            "typeName": element.name,
+        });
+        if let Some(version) = element.version {
+            entry["version"] = json!(version);
+        }
+        types.insert(pointer.clone(), entry);
+    }
+
+    if let Some(container) = element.container() {
+        return match container {
+            Container::Sequence(items) => Value::Array(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| data(item, format!("{pointer}/{i}"), types))
+                    .collect(),
+            ),
+            Container::Optional(value) => value
+                .map(|value| data(value, pointer, types))
+                .unwrap_or(Value::Null),
+            Container::Map(pairs) => match crate::container::unique_keys(&pairs) {
+                Some(keys) => Value::Object(
+                    keys.into_iter()
+                        .zip(&pairs)
+                        .map(|(key, (_, value))| {
+                            let value = data(value, format!("{pointer}/{}", escape(&key)), types);
+                            (key, value)
+                        })
+                        .collect(),
+                ),
+                None => Value::Array(
+                    pairs
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (key, value))| {
+                            Value::Array(vec![
+                                data(key, format!("{pointer}/{i}/0"), types),
+                                data(value, format!("{pointer}/{i}/1"), types),
+                            ])
+                        })
+                        .collect(),
+                ),
+            },
+        };
+    }
+
+    let value = element.az_value().map(|value| value.to_json());
+    if element.elements.is_empty() {
+        return value.unwrap_or_else(|| Value::Object(Map::new()));
+    }
+
+    let mut object = Map::new();
+    if let Some(value) = value {
+        object.insert("$value".into(), value);
+    }
+    fields(element, &pointer, types, &mut object);
+    Value::Object(object)
+}
+
+/// Adds the fields of `element` and of its base classes to `object`. Repeated field names
+/// are collected into an array.
+fn fields(
+    element: &Element,
+    pointer: &str,
+    types: &mut Option<&mut Map<String, Value>>,
+    object: &mut Map<String, Value>,
+) {
+    let mut members = vec![];
+    members_of(element, &mut members);
+    let mut counts = HashMap::<&str, usize>::new();
+    for (field, _) in &members {
+        *counts.entry(field).or_default() += 1;
+    }
+
+    for (field, child) in &members {
+        let pointer = format!("{pointer}/{}", escape(field));
+        if counts[field.as_str()] == 1 {
+            let value = data(child, pointer, types);
+            object.insert(field.to_owned(), value);
+            continue;
+        }
+        let Value::Array(values) = object
+            .entry(field.to_owned())
+            .or_insert_with(|| Value::Array(vec![]))
+        else {
+            unreachable!("repeated fields are arrays")
+        };
+        let index = values.len();
+        values.push(data(child, format!("{pointer}/{index}"), types));
+    }
+}
+
+/// The fields of `element` with the fields of its base classes in place of them.
+pub(crate) fn members_of<'a, E: Node>(element: &'a E, members: &mut Vec<(String, &'a E)>) {
+    for child in element.children() {
+        let field = child.field_name().unwrap_or_else(|| child.path_segment());
+        let base = child
+            .name_crc()
+            .is_some_and(|crc| BASE_CLASSES.contains(&crc));
+        match base && child.data().is_none() {
+            true => members_of(child, members),
+            false => members.push((field, child)),
+        }
+    }
+}
+
+/// JSON pointer escaping of a key.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{element, field_crc};
+    use utils::types::{AZSTD_STRING, FLOAT, INT};
+    use uuid::Uuid;
+
+    #[test]
+    fn data_only() {
+        let root = Element {
+            name_crc: None,
+            field: None,
+            name: "Item".into(),
+            version: Some(2),
+            ..element(
+                "",
+                Uuid::from_u128(1),
+                None,
+                vec![
+                    element(
+                        "BaseClass1",
+                        Uuid::from_u128(2),
+                        None,
+                        vec![element(
+                            "m_id",
+                            INT,
+                            Some(5i32.to_be_bytes().to_vec()),
+                            vec![],
+                        )],
+                    ),
+                    element("m_name", AZSTD_STRING, Some(b"Iron".to_vec()), vec![]),
+                    element(
+                        "m_weights",
+                        Uuid::from_u128(3),
+                        None,
+                        vec![
+                            element(
+                                "element",
+                                FLOAT,
+                                Some(0.5f32.to_be_bytes().to_vec()),
+                                vec![],
+                            ),
+                            element("element", FLOAT, Some(2f32.to_be_bytes().to_vec()), vec![]),
+                        ],
+                    ),
+                    element("m_tag", AZSTD_STRING, Some(b"a".to_vec()), vec![]),
+                    element("m_tag", AZSTD_STRING, Some(b"b".to_vec()), vec![]),
+                    element("m_tag", AZSTD_STRING, Some(b"c".to_vec()), vec![]),
+                ],
+            )
+        };
+        let stream = ObjectStream {
+            version: 3,
+            elements: vec![root],
+            ..Default::default()
+        };
+
+        let (data, types) = stream.to_data_with_types();
+        assert_eq!(
+            data,
+            json!({
+                "m_id": 5,
+                "m_name": "Iron",
+                "m_weights": [0.5, 2.0],
+                "m_tag": ["a", "b", "c"],
+            })
+        );
+        assert_eq!(stream.to_data(), data);
+        assert_eq!(types[""]["typeName"], "Item");
+        assert_eq!(types[""]["version"], 2);
+        assert_eq!(types["/m_weights/1"]["typeId"], braced(&FLOAT));
+        for pointer in ["/m_tag/0", "/m_tag/1", "/m_tag/2", "/m_id"] {
+            assert!(types.get(pointer).is_some(), "{pointer}");
+        }
+        assert!(types.get("/m_tag").is_none());
+
+        // without hashes fields are keyed by their name CRC, base classes still flatten
+        fn unresolve(element: &mut Element) {
+            element.field = None;
+            element.elements.iter_mut().for_each(unresolve);
+        }
+        let mut stream = stream;
+        stream.elements.iter_mut().for_each(unresolve);
+        let key = |field: &str| format!("0x{:08x}", field_crc(field));
+        let data = stream.to_data();
+        assert_eq!(data[key("m_id")], 5);
+        assert_eq!(data[key("m_name")], "Iron");
+        assert!(data.get(key("BaseClass1")).is_none());
+    }
+}
@@ -1,359 +1,744 @@
-// use crate::{
-//     error::Error, ST_BINARYFLAG_HAS_NAME, ST_BINARYFLAG_HAS_VALUE, ST_BINARYFLAG_HAS_VERSION,
-// };
-// use serde::de;
-// use std::{fmt, io::Read};
-
-// pub struct Deserializer<R> {
-//     reader: R,
-//     version: u32,
-// }
-
-// impl<R: Read> Deserializer<R> {
-//     pub fn new(reader: R, version: u32) -> Self {
-//         Deserializer { reader, version }
-//     }
-// }
-
-// impl<'de, 'a, R: Read> de::Deserializer<'de> for &'a mut Deserializer<R> {
-//     type Error = Error;
-
-//     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         let mut buf = [0; 1];
-//         self.reader.read_exact(&mut buf).map_err(Error::Io)?;
-//         visitor.visit_u8(buf[0])
-//     }
-
-//     fn deserialize_struct<V>(
-//         self,
-//         _name: &'static str,
-//         _fields: &'static [&'static str],
-//         visitor: V,
-//     ) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         visitor.visit_map(StructAccess::new(self)?)
-//     }
-
-//     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         let mut buf = [0; 4];
-//         self.reader.read_exact(&mut buf).map_err(Error::Io)?;
-//         visitor.visit_u32(u32::from_be_bytes(buf))
-//     }
-
-//     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         let mut buf = [0; 4];
-//         self.reader.read_exact(&mut buf).map_err(Error::Io)?;
-//         let len = u32::from_be_bytes(buf) as usize;
-//         let mut buf = vec![0; len];
-//         self.reader.read_exact(&mut buf).map_err(Error::Io)?;
-//         visitor.visit_bytes(&buf)
-//     }
-
-//     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_unit_struct<V>(
-//         self,
-//         name: &'static str,
-//         visitor: V,
-//     ) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_newtype_struct<V>(
-//         self,
-//         name: &'static str,
-//         visitor: V,
-//     ) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_tuple_struct<V>(
-//         self,
-//         name: &'static str,
-//         len: usize,
-//         visitor: V,
-//     ) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_enum<V>(
-//         self,
-//         name: &'static str,
-//         variants: &'static [&'static str],
-//         visitor: V,
-//     ) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::Visitor<'de>,
-//     {
-//         todo!()
-//     }
-
-//     // Implement other methods...
-// }
-
-// struct StructAccess<'a, R: 'a> {
-//     de: &'a mut Deserializer<R>,
-//     flags: u8,
-// }
-
-// impl<'a, R: Read> StructAccess<'a, R> {
-//     fn new(de: &'a mut Deserializer<R>) -> Result<Self, Error> {
-//         let mut buf = [0; 1];
-//         de.reader.read_exact(&mut buf).map_err(Error::Io)?;
-//         let flags = buf[0];
-//         Ok(StructAccess { de, flags })
-//     }
-// }
-
-// impl<'de, 'a, R: Read> de::MapAccess<'de> for StructAccess<'a, R> {
-//     type Error = Error;
-
-//     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
-//     where
-//         K: de::DeserializeSeed<'de>,
-//     {
-//         // Use an enum to represent the fields
-//         enum Field {
-//             Flags,
-//             NameCrc,
-//             Version,
-//             Id,
-//             Specialization,
-//             DataSize,
-//             Data,
-//             Elements,
-//         }
-
-//         // Implement a visitor for the field names
-//         struct FieldVisitor;
-
-//         impl<'de> de::Visitor<'de> for FieldVisitor {
-//             type Value = Field;
-
-//             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-//                 formatter.write_str("a field name")
-//             }
-
-//             fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
-//             where
-//                 E: de::Error,
-//             {
-//                 match value {
-//                     "flags" => Ok(Field::Flags),
-//                     "name_crc" => Ok(Field::NameCrc),
-//                     "version" => Ok(Field::Version),
-//                     "id" => Ok(Field::Id),
-//                     "specialization" => Ok(Field::Specialization),
-//                     "data_size" => Ok(Field::DataSize),
-//                     "data" => Ok(Field::Data),
-//                     "elements" => Ok(Field::Elements),
-//                     _ => Err(E::unknown_field(value, FIELDS)),
-//                 }
-//             }
-//         }
-
-//         // Deserialize the field name
-//         let field: Field = seed.deserialize(FieldVisitor)?;
-
-//         // Determine which field to return next based on flags and previous fields
-//         match field {
-//             Field::Flags => Ok(Some(Field::Flags)),
-//             Field::NameCrc if self.flags & ST_BINARYFLAG_HAS_NAME > 0 => Ok(Some(Field::NameCrc)),
-//             Field::Version if self.flags & ST_BINARYFLAG_HAS_VERSION > 0 => {
-//                 Ok(Some(Field::Version))
-//             }
-//             Field::Id => Ok(Some(Field::Id)),
-//             Field::Specialization if self.de.version == 2 => Ok(Some(Field::Specialization)),
-//             Field::DataSize if self.flags & ST_BINARYFLAG_HAS_VALUE > 0 => {
-//                 Ok(Some(Field::DataSize))
-//             }
-//             Field::Data if self.flags & ST_BINARYFLAG_HAS_VALUE > 0 => Ok(Some(Field::Data)),
-//             Field::Elements => Ok(Some(Field::Elements)),
-//             _ => Ok(None),
-//         }
-//     }
-
-//     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
-//     where
-//         V: de::DeserializeSeed<'de>,
-//     {
-//         seed.deserialize(&mut *self.de)
-//     }
-// }
-
-// // Define the list of fields for error reporting
-// const FIELDS: &[&str] = &[
-//     "flags",
-//     "name_crc",
-//     "version",
-//     "id",
-//     "specialization",
-//     "data_size",
-//     "data",
-//     "elements",
-// ];
+use crate::{
+    braced, error::Error, error::Result, field_crc, from_reader, slice::BASE_CLASSES, Element,
+};
+use serde::de::{
+    self,
+    value::{StringDeserializer, U64Deserializer},
+    DeserializeOwned, DeserializeSeed, EnumAccess, Error as _, IntoDeserializer, MapAccess,
+    SeqAccess, Unexpected, VariantAccess, Visitor,
+};
+use std::io::Cursor;
+use utils::{crc32, lumberyard::LumberyardSource, types::*};
+use uuid::Uuid;
+
+/// Deserializes the root element of a binary ObjectStream into `T`.
+///
+/// Struct fields are matched against element fields by name, resolved through `hashes` when
+/// available and otherwise by comparing the lowercase CRC of the Rust field name with the
+/// element's name CRC. Fields renamed to a hex CRC (`#[serde(rename = "0x1a2b3c4d")]`) match
+/// the name CRC directly, for fields whose names aren't known.
+pub fn from_slice<T>(bytes: &[u8], hashes: Option<&'static LumberyardSource>) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let stream = from_reader(&mut Cursor::new(bytes), hashes)?;
+    let root = stream
+        .elements
+        .first()
+        .ok_or_else(|| Error::custom("ObjectStream has no root element"))?;
+    from_element(root)
+}
+
+/// Deserializes `T` from an already parsed [`Element`].
+pub fn from_element<'de, T>(element: &'de Element) -> Result<T>
+where
+    T: de::Deserialize<'de>,
+{
+    T::deserialize(Deserializer::new(element))
+}
+
+pub struct Deserializer<'de> {
+    element: &'de Element,
+}
+
+impl<'de> Deserializer<'de> {
+    pub fn new(element: &'de Element) -> Self {
+        Deserializer { element }
+    }
+
+    fn data(&self) -> &'de [u8] {
+        self.element.data.as_deref().unwrap_or_default()
+    }
+
+    fn signed(&self) -> Result<i64> {
+        let data = self.data();
+        Ok(match data.len() {
+            1 => i8::from_be_bytes(data.try_into().unwrap()) as i64,
+            2 => i16::from_be_bytes(data.try_into().unwrap()) as i64,
+            4 => i32::from_be_bytes(data.try_into().unwrap()) as i64,
+            8 => i64::from_be_bytes(data.try_into().unwrap()),
+            len => return Err(Error::invalid_length(len, &"1, 2, 4 or 8 bytes")),
+        })
+    }
+
+    fn unsigned(&self) -> Result<u64> {
+        let data = self.data();
+        Ok(match data.len() {
+            1 => data[0] as u64,
+            2 => u16::from_be_bytes(data.try_into().unwrap()) as u64,
+            4 => u32::from_be_bytes(data.try_into().unwrap()) as u64,
+            8 => u64::from_be_bytes(data.try_into().unwrap()),
+            len => return Err(Error::invalid_length(len, &"1, 2, 4 or 8 bytes")),
+        })
+    }
+
+    fn float(&self) -> Result<f64> {
+        let data = self.data();
+        Ok(match data.len() {
+            4 => f32::from_be_bytes(data.try_into().unwrap()) as f64,
+            8 => f64::from_be_bytes(data.try_into().unwrap()),
+            len => return Err(Error::invalid_length(len, &"4 or 8 bytes")),
+        })
+    }
+
+    fn is_math(&self) -> bool {
+        matches!(
+            self.element.id,
+            VECTOR2
+                | VECTOR3
+                | VECTOR4
+                | TRANSFORM
+                | QUATERNION
+                | COLOR
+                | MATRIX3X3
+                | MATRIX4X4
+                | AABB
+        )
+    }
+
+    /// Containers store their items as children named `element`.
+    fn is_sequence(&self) -> bool {
+        let element = crc32("element");
+        !self.element.elements.is_empty()
+            && self
+                .element
+                .elements
+                .iter()
+                .all(|child| child.name_crc == Some(element))
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.element.data.is_none() {
+            return match self.element.elements.is_empty() {
+                true => visitor.visit_unit(),
+                false if self.is_sequence() => self.deserialize_seq(visitor),
+                false => self.deserialize_map(visitor),
+            };
+        }
+
+        match self.element.id {
+            CHAR | SIGNED_CHAR | AZ_S8 | SHORT | INT | LONG | AZ_S64 => {
+                visitor.visit_i64(self.signed()?)
+            }
+            UNSIGNED_CHAR | UNSIGNED_SHORT | UNSIGNED_INT | UNSIGNED_LONG | AZ_U64 => {
+                visitor.visit_u64(self.unsigned()?)
+            }
+            FLOAT | DOUBLE => visitor.visit_f64(self.float()?),
+            BOOL => self.deserialize_bool(visitor),
+            AZ_UUID => self.deserialize_string(visitor),
+            _ if self.is_math() => self.deserialize_seq(visitor),
+            _ => match std::str::from_utf8(self.data()) {
+                Ok(string) => visitor.visit_borrowed_str(string),
+                Err(_) => visitor.visit_borrowed_bytes(self.data()),
+            },
+        }
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_bool(self.unsigned()? != 0)
+    }
+
+    fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.signed()?)
+    }
+
+    fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.signed()?)
+    }
+
+    fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.signed()?)
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i64(self.signed()?)
+    }
+
+    fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u64(self.unsigned()?)
+    }
+
+    fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u64(self.unsigned()?)
+    }
+
+    fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u64(self.unsigned()?)
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u64(self.unsigned()?)
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f64(self.float()?)
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_f64(self.float()?)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.element.id == AZ_UUID {
+            return self.deserialize_string(visitor);
+        }
+        let string = std::str::from_utf8(self.data()).map_err(Error::custom)?;
+        visitor.visit_borrowed_str(string)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.element.id == AZ_UUID {
+            let uuid = Uuid::from_slice(self.data()).map_err(Error::custom)?;
+            return visitor.visit_string(braced(&uuid));
+        }
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_borrowed_bytes(self.data())
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.element.elements.is_empty() && self.is_math() {
+            return visitor.visit_seq(FloatAccess {
+                chunks: self.data().chunks_exact(4),
+            });
+        }
+        visitor.visit_seq(ElementsAccess {
+            elements: self.element.elements.iter(),
+        })
+    }
+
+    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    /// `AZStd::unordered_map` and friends are sequences of pairs (`value1`, `value2`), anything
+    /// else is read as a map of field name to element.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.is_sequence() {
+            let (value1, value2) = (crc32("value1"), crc32("value2"));
+            let pairs = self
+                .element
+                .elements
+                .iter()
+                .map(|pair| {
+                    let key = pair.elements.iter().find(|e| e.name_crc == Some(value1));
+                    let value = pair.elements.iter().find(|e| e.name_crc == Some(value2));
+                    key.zip(value)
+                })
+                .collect::<Option<Vec<_>>>();
+            if let Some(pairs) = pairs {
+                return visitor.visit_map(PairAccess {
+                    pairs: pairs.into_iter(),
+                    value: None,
+                });
+            }
+        }
+        visitor.visit_map(FieldAccess::new(self.element, &[]))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(FieldAccess::new(self.element, fields))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_unit()
+    }
+}
+
+/// Lumberyard enums are stored as their underlying integer, which serde reads as the variant
+/// index. String-typed values are matched against the variant names.
+impl<'de> EnumAccess<'de> for Deserializer<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = match self.element.id {
+            // variant identifiers only take unsigned indices
+            CHAR | SIGNED_CHAR | AZ_S8 | SHORT | INT | LONG | AZ_S64 => {
+                let signed = self.signed()?;
+                let index = u64::try_from(signed).map_err(|_| {
+                    Error::invalid_value(Unexpected::Signed(signed), &"a variant index")
+                })?;
+                let index: U64Deserializer<Error> = index.into_deserializer();
+                seed.deserialize(index)?
+            }
+            AZSTD_STRING | AZSTD_BASIC_STRING | AZSTD_BASIC_STRING_VIEW => {
+                seed.deserialize(Deserializer::new(self.element))?
+            }
+            // enums stored under their own type id hold the raw integer
+            _ => {
+                let index: U64Deserializer<Error> = self.unsigned()?.into_deserializer();
+                seed.deserialize(index)?
+            }
+        };
+        Ok((value, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Deserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(FieldAccess::new(self.element, fields))
+    }
+}
+
+struct ElementsAccess<'de> {
+    elements: std::slice::Iter<'de, Element>,
+}
+
+impl<'de> SeqAccess<'de> for ElementsAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.elements
+            .next()
+            .map(|element| seed.deserialize(Deserializer::new(element)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+struct FloatAccess<'de> {
+    chunks: std::slice::ChunksExact<'de, u8>,
+}
+
+impl<'de> SeqAccess<'de> for FloatAccess<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        self.chunks
+            .next()
+            .map(|chunk| {
+                let value = f32::from_be_bytes(chunk.try_into().unwrap());
+                seed.deserialize(value.into_deserializer())
+            })
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.chunks.len())
+    }
+}
+
+struct PairAccess<'de> {
+    pairs: std::vec::IntoIter<(&'de Element, &'de Element)>,
+    value: Option<&'de Element>,
+}
+
+impl<'de> MapAccess<'de> for PairAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let Some((key, value)) = self.pairs.next() else {
+            return Ok(None);
+        };
+        self.value = Some(value);
+        seed.deserialize(Deserializer::new(key)).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Error::custom("value requested before key"))?;
+        seed.deserialize(Deserializer::new(value))
+    }
+}
+
+/// Walks the children of a class element as `field name -> element`, flattening base classes
+/// (`BaseClass1`, ...) into their derived class like the serialize context does.
+struct FieldAccess<'de> {
+    members: std::vec::IntoIter<(String, &'de Element)>,
+    value: Option<&'de Element>,
+}
+
+impl<'de> FieldAccess<'de> {
+    fn new(element: &'de Element, fields: &'static [&'static str]) -> Self {
+        let fields = fields
+            .iter()
+            .map(|field| (field_crc(field), *field))
+            .collect::<Vec<_>>();
+        let mut members = vec![];
+        collect_members(element, &fields, &mut members);
+        Self {
+            members: members.into_iter(),
+            value: None,
+        }
+    }
+}
+
+fn collect_members<'de>(
+    element: &'de Element,
+    fields: &[(u32, &'static str)],
+    members: &mut Vec<(String, &'de Element)>,
+) {
+    for child in &element.elements {
+        let Some(crc) = child.name_crc else {
+            continue;
+        };
+
+        if let Some((_, field)) = fields.iter().find(|(field, _)| *field == crc) {
+            members.push((field.to_string(), child));
+            continue;
+        }
+
+        if BASE_CLASSES.contains(&crc) {
+            collect_members(child, fields, members);
+            continue;
+        }
+
+        match child.field.as_deref() {
+            Some(field) => members.push((field.to_owned(), child)),
+            None => members.push((format!("0x{:08x}", crc), child)),
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for FieldAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let Some((field, element)) = self.members.next() else {
+            return Ok(None);
+        };
+        self.value = Some(element);
+        let key: StringDeserializer<Error> = field.into_deserializer();
+        seed.deserialize(key).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| Error::custom("value requested before key"))?;
+        seed.deserialize(Deserializer::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.members.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element;
+    use serde::{Deserialize, Serialize};
+
+    fn floats(values: &[f32]) -> Option<Vec<u8>> {
+        Some(values.iter().flat_map(|f| f.to_be_bytes()).collect())
+    }
+
+    #[test]
+    fn structs() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Attributes {
+            m_name: String,
+            m_level: i32,
+            m_scale: f32,
+            #[serde(rename = "0xdeadbeef")]
+            unknown: bool,
+            m_tags: Vec<u8>,
+            m_position: [f32; 3],
+            m_bounds: [f32; 6],
+            m_missing: Option<u64>,
+        }
+
+        let root = element(
+            "",
+            Uuid::nil(),
+            None,
+            vec![
+                element("m_name", Uuid::nil(), Some(b"Iron".to_vec()), vec![]),
+                element("m_level", INT, Some((-3i32).to_be_bytes().to_vec()), vec![]),
+                element(
+                    "m_scale",
+                    FLOAT,
+                    Some(1.5f32.to_be_bytes().to_vec()),
+                    vec![],
+                ),
+                element("0xdeadbeef", BOOL, Some(vec![1]), vec![]),
+                element(
+                    "m_tags",
+                    Uuid::nil(),
+                    None,
+                    vec![
+                        element("element", UNSIGNED_CHAR, Some(vec![4]), vec![]),
+                        element("element", UNSIGNED_CHAR, Some(vec![2]), vec![]),
+                    ],
+                ),
+                element("m_position", VECTOR3, floats(&[1., 2., 3.]), vec![]),
+                element("m_bounds", AABB, floats(&[0., 0., 0., 1., 2., 3.]), vec![]),
+            ],
+        );
+
+        let attributes: Attributes = from_element(&root).unwrap();
+        assert_eq!(
+            attributes,
+            Attributes {
+                m_name: "Iron".into(),
+                m_level: -3,
+                m_scale: 1.5,
+                unknown: true,
+                m_tags: vec![4, 2],
+                m_position: [1., 2., 3.],
+                m_bounds: [0., 0., 0., 1., 2., 3.],
+                m_missing: None,
+            }
+        );
+    }
+
+    #[test]
+    fn unit_enums() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        enum Stance {
+            Idle,
+            Crouch,
+            Prone,
+        }
+
+        let unsigned = crate::to_element(&Stance::Crouch).unwrap();
+        assert_eq!(unsigned.id, UNSIGNED_INT);
+        assert_eq!(from_element::<Stance>(&unsigned).unwrap(), Stance::Crouch);
+
+        let int = |value: i32| element("", INT, Some(value.to_be_bytes().to_vec()), vec![]);
+        assert_eq!(from_element::<Stance>(&int(2)).unwrap(), Stance::Prone);
+        assert!(from_element::<Stance>(&int(-1)).is_err());
+
+        let named = element("", AZSTD_STRING, Some(b"Idle".to_vec()), vec![]);
+        assert_eq!(from_element::<Stance>(&named).unwrap(), Stance::Idle);
+
+        // 0x00000002 is valid UTF-8 but not a string type, so it's read as an index
+        let custom = Uuid::from_u128(0x99);
+        let own = element("", custom, Some(2u32.to_be_bytes().to_vec()), vec![]);
+        assert_eq!(from_element::<Stance>(&own).unwrap(), Stance::Prone);
+    }
+
+    #[test]
+    fn base_classes_and_maps() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Component {
+            #[serde(rename = "Id")]
+            id: u64,
+            m_values: std::collections::BTreeMap<String, i32>,
+        }
+
+        let pair = element(
+            "element",
+            AZSTD_PAIR,
+            None,
+            vec![
+                element("value1", AZSTD_STRING, Some(b"a".to_vec()), vec![]),
+                element("value2", INT, Some(1i32.to_be_bytes().to_vec()), vec![]),
+            ],
+        );
+        let id = element("Id", AZ_U64, Some(7u64.to_be_bytes().to_vec()), vec![]);
+        let root = element(
+            "",
+            Uuid::nil(),
+            None,
+            vec![
+                // base classes are found by name CRC, their field name isn't resolved
+                Element {
+                    field: None,
+                    ..element("BaseClass1", Uuid::nil(), None, vec![id])
+                },
+                element("m_values", AZSTD_MAP, None, vec![pair]),
+            ],
+        );
+
+        let component: Component = from_element(&root).unwrap();
+        assert_eq!(component.id, 7);
+        assert_eq!(component.m_values.get("a"), Some(&1));
+    }
+}
@@ -0,0 +1,308 @@
+//! Differences between two streams, by element instead of by line.
+//!
+//! Children are matched by name crc (or type for unnamed elements), then by entity or
+//! component id, map key or position among the children with the same name. Values are
+//! compared decoded, so the text formatting of floats doesn't matter.
+
+use crate::{braced, container::Container, slice::persistent_id, Element, Node, ObjectStream};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Change {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Removed {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        from: Value,
+        to: Value,
+    },
+}
+
+impl Change {
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {path} = {value}"),
+            Change::Removed { path, value } => write!(f, "- {path} = {value}"),
+            Change::Changed { path, from, to } => write!(f, "~ {path}: {from} -> {to}"),
+        }
+    }
+}
+
+impl ObjectStream {
+    /// The changes from `self` to `other`, in document order.
+    pub fn diff(&self, other: &ObjectStream) -> Vec<Change> {
+        let mut changes = vec![];
+        if self.version != other.version {
+            changes.push(Change::Changed {
+                path: "@version".into(),
+                from: self.version.into(),
+                to: other.version.into(),
+            });
+        }
+        diff_children(
+            "",
+            (&self.elements, None),
+            (&other.elements, None),
+            &mut changes,
+        );
+        changes
+    }
+}
+
+/// What tells apart children with the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Discriminator {
+    Id(u64),
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    name: Option<u32>,
+    /// Type of unnamed elements.
+    id: Option<Uuid>,
+    discriminator: Discriminator,
+}
+
+/// The children of an element with their keys and path segments.
+fn keyed<'e>(
+    children: &'e [Element],
+    container: Option<Container<'e>>,
+) -> Vec<(Key, String, &'e Element)> {
+    let keys = match &container {
+        Some(Container::Map(pairs)) => crate::container::unique_keys(pairs),
+        _ => None,
+    };
+    let mut counts = HashMap::<(Option<u32>, Option<Uuid>), usize>::new();
+    for child in children {
+        *counts.entry(name_of(child)).or_default() += 1;
+    }
+
+    let mut seen = HashMap::<(Option<u32>, Option<Uuid>), usize>::new();
+    children
+        .iter()
+        .enumerate()
+        .map(|(i, child)| {
+            let name = name_of(child);
+            let index = seen.entry(name).or_default();
+            *index += 1;
+            let segment = child.field_name().unwrap_or_else(|| child.path_segment());
+            let (discriminator, segment) = match (persistent_id(child), &keys) {
+                (Some(id), _) => (Discriminator::Id(id), format!("{segment}[#{id}]")),
+                (None, Some(keys)) => (
+                    Discriminator::Key(keys[i].clone()),
+                    format!("{segment}[{:?}]", keys[i]),
+                ),
+                (None, None) if counts[&name] > 1 || container.is_some() => (
+                    Discriminator::Index(*index - 1),
+                    format!("{segment}[{}]", *index - 1),
+                ),
+                (None, None) => (Discriminator::Index(0), segment),
+            };
+            let key = Key {
+                name: name.0,
+                id: name.1,
+                discriminator,
+            };
+            (key, segment, child)
+        })
+        .collect()
+}
+
+fn name_of(element: &Element) -> (Option<u32>, Option<Uuid>) {
+    match element.name_crc {
+        Some(crc) => (Some(crc), None),
+        None => (None, Some(element.id)),
+    }
+}
+
+fn diff_children<'e>(
+    path: &str,
+    (old, old_container): (&'e [Element], Option<Container<'e>>),
+    (new, new_container): (&'e [Element], Option<Container<'e>>),
+    changes: &mut Vec<Change>,
+) {
+    let old = keyed(old, old_container);
+    let new = keyed(new, new_container);
+    let new_by_key = new
+        .iter()
+        .map(|(key, _, element)| (key, *element))
+        .collect::<HashMap<_, _>>();
+    let old_keys = old.iter().map(|(key, ..)| key).collect::<HashSet<_>>();
+
+    for (key, segment, element) in &old {
+        let path = join(path, segment);
+        match new_by_key.get(key) {
+            Some(other) => diff_element(&path, element, other, changes),
+            None => changes.push(Change::Removed {
+                value: crate::data::element_data(element),
+                path,
+            }),
+        }
+    }
+    for (key, segment, element) in &new {
+        if !old_keys.contains(&key) {
+            changes.push(Change::Added {
+                path: join(path, segment),
+                value: crate::data::element_data(element),
+            });
+        }
+    }
+}
+
+fn diff_element(path: &str, old: &Element, new: &Element, changes: &mut Vec<Change>) {
+    if old.id != new.id {
+        changes.push(Change::Changed {
+            path: format!("{path}@type"),
+            from: type_name(old).into(),
+            to: type_name(new).into(),
+        });
+        changes.push(Change::Changed {
+            path: path.to_owned(),
+            from: crate::data::element_data(old),
+            to: crate::data::element_data(new),
+        });
+        return;
+    }
+    if old.version != new.version {
+        changes.push(Change::Changed {
+            path: format!("{path}@version"),
+            from: old.version.into(),
+            to: new.version.into(),
+        });
+    }
+    if old.az_value() != new.az_value() {
+        let value = |element: &Element| {
+            element
+                .az_value()
+                .map(|value| value.to_json())
+                .unwrap_or(Value::Null)
+        };
+        changes.push(Change::Changed {
+            path: path.to_owned(),
+            from: value(old),
+            to: value(new),
+        });
+    }
+    diff_children(
+        path,
+        (&old.elements, old.container()),
+        (&new.elements, new.container()),
+        changes,
+    );
+}
+
+fn type_name(element: &Element) -> String {
+    match element.name.is_empty() {
+        true => braced(&element.id),
+        false => element.name.clone(),
+    }
+}
+
+fn join(path: &str, segment: &str) -> String {
+    match path.is_empty() {
+        true => segment.to_owned(),
+        false => format!("{path}/{segment}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{element, slice::ENTITY_ID};
+    use serde_json::json;
+    use utils::types::{AZSTD_STRING, AZSTD_VECTOR, AZ_U64, FLOAT, INT};
+
+    fn item(id: u64, name: &str) -> Element {
+        let id = element("id", AZ_U64, Some(id.to_be_bytes().to_vec()), vec![]);
+        element(
+            "element",
+            Uuid::from_u128(2),
+            None,
+            vec![
+                element("Id", ENTITY_ID, None, vec![id]),
+                element("m_name", AZSTD_STRING, Some(name.into()), vec![]),
+            ],
+        )
+    }
+
+    fn stream(weight: f32, items: Vec<Element>, tags: &[i32]) -> ObjectStream {
+        ObjectStream {
+            version: 3,
+            elements: vec![element(
+                "root",
+                Uuid::from_u128(1),
+                None,
+                vec![
+                    element(
+                        "m_weight",
+                        FLOAT,
+                        Some(weight.to_be_bytes().to_vec()),
+                        vec![],
+                    ),
+                    element("m_items", AZSTD_VECTOR, None, items),
+                    element(
+                        "m_tags",
+                        AZSTD_VECTOR,
+                        None,
+                        tags.iter()
+                            .map(|t| {
+                                element("element", INT, Some(t.to_be_bytes().to_vec()), vec![])
+                            })
+                            .collect(),
+                    ),
+                ],
+            )],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn diff() {
+        let old = stream(0.5, vec![item(1, "Tree"), item(2, "Rock")], &[1, 2]);
+        let new = stream(0.5, vec![item(2, "Rock"), item(1, "Tree")], &[1, 2]);
+        assert!(old.diff(&new).is_empty(), "reordered entities are the same");
+
+        let new = stream(0.75, vec![item(2, "Rock"), item(3, "Bush")], &[1, 3, 4]);
+        let changes = old.diff(&new);
+        let text = changes.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            text,
+            [
+                "~ root/m_weight: 0.5 -> 0.75",
+                "- root/m_items/element[#1] = {\"Id\":{\"id\":1},\"m_name\":\"Tree\"}",
+                "+ root/m_items/element[#3] = {\"Id\":{\"id\":3},\"m_name\":\"Bush\"}",
+                "~ root/m_tags/element[1]: 2 -> 3",
+                "+ root/m_tags/element[2] = 4",
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&changes[0]).unwrap(),
+            json!({"op": "changed", "path": "root/m_weight", "from": 0.5, "to": 0.75})
+        );
+    }
+}
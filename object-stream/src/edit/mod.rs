@@ -0,0 +1,187 @@
+//! Changing values of a stream in place, addressed with [`query`](crate::query) expressions.
+//!
+//! Edited elements get their flags and size fields recomputed, everything else is written
+//! back with the bytes it was read with.
+
+use crate::{error::Error, error::Result, query::Query, Element, Node, ObjectStream};
+use serde_json::Value;
+use std::collections::HashSet;
+use utils::types::AzValue;
+
+impl ObjectStream {
+    /// Sets every element matched by `expr` to `value`, given in the XML text form of the
+    /// element type. Returns how many elements were changed. Every match is checked first, on
+    /// an error the stream is left as it was.
+    pub fn set_value(&mut self, expr: &str, value: &str) -> Result<usize> {
+        let paths = self.paths(expr)?;
+        let values = paths
+            .iter()
+            .map(|indices| {
+                let element = self.element(indices).expect("selected element");
+                if element.data.is_none() {
+                    return Err(Error::Message(format!(
+                        "`{}` has no value to set",
+                        element.path_segment()
+                    )));
+                }
+                Ok(AzValue::parse(
+                    &element.id,
+                    &Value::String(value.to_owned()),
+                )?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for (indices, value) in paths.iter().zip(&values) {
+            self.element_mut(indices)
+                .expect("selected element")
+                .set_value(value);
+        }
+        Ok(paths.len())
+    }
+
+    /// Removes every element matched by `expr` with its children. Returns how many elements
+    /// were removed.
+    pub fn remove(&mut self, expr: &str) -> Result<usize> {
+        let mut paths = self.paths(expr)?;
+        // Later siblings and descendants first so the remaining paths stay valid.
+        paths.sort_unstable_by(|a, b| b.cmp(a));
+        for indices in &paths {
+            let (last, parent) = indices.split_last().expect("non-empty path");
+            let siblings = match parent.is_empty() {
+                true => &mut self.elements,
+                false => &mut self.element_mut(parent).expect("selected parent").elements,
+            };
+            siblings.remove(*last);
+        }
+        Ok(paths.len())
+    }
+
+    fn element(&self, indices: &[usize]) -> Option<&Element> {
+        let (first, rest) = indices.split_first()?;
+        rest.iter()
+            .try_fold(self.elements.get(*first)?, |element, i| {
+                element.elements.get(*i)
+            })
+    }
+
+    /// The element at `indices`, each one the position among the children of the previous.
+    pub fn element_mut(&mut self, indices: &[usize]) -> Option<&mut Element> {
+        let (first, rest) = indices.split_first()?;
+        rest.iter()
+            .try_fold(self.elements.get_mut(*first)?, |element, i| {
+                element.elements.get_mut(*i)
+            })
+    }
+
+    /// Index paths of the elements matched by `expr`, in document order.
+    fn paths(&self, expr: &str) -> Result<Vec<Vec<usize>>> {
+        let matched = Query::parse(expr)?
+            .select(&self.elements)
+            .into_iter()
+            .map(|m| m.element as *const Element)
+            .collect::<HashSet<_>>();
+        let mut paths = vec![];
+        index_paths(&self.elements, &matched, &mut vec![], &mut paths);
+        Ok(paths)
+    }
+}
+
+impl Element {
+    /// Replaces the data, updating the flags and size field written with it.
+    pub fn set_value(&mut self, value: &AzValue) {
+        self.data = Some(value.to_bytes());
+        self.update_flags();
+    }
+}
+
+fn index_paths(
+    elements: &[Element],
+    matched: &HashSet<*const Element>,
+    path: &mut Vec<usize>,
+    paths: &mut Vec<Vec<usize>>,
+) {
+    for (i, element) in elements.iter().enumerate() {
+        path.push(i);
+        if matched.contains(&(element as *const Element)) {
+            paths.push(path.clone());
+        }
+        index_paths(&element.elements, matched, path, paths);
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{element, from_reader};
+    use utils::types::{AZSTD_STRING, AZSTD_VECTOR, FLOAT, INT};
+    use uuid::Uuid;
+
+    fn roundtrip(stream: &ObjectStream) -> (Vec<u8>, ObjectStream) {
+        let mut buf = vec![];
+        stream.to_writer(&mut buf).unwrap();
+        let read = from_reader(&mut buf.as_slice(), None).unwrap();
+        (buf, read)
+    }
+
+    #[test]
+    fn edit() {
+        let mut root = element(
+            "root",
+            Uuid::from_u128(1),
+            None,
+            vec![
+                element(
+                    "m_maxHealth",
+                    FLOAT,
+                    Some(100f32.to_be_bytes().to_vec()),
+                    vec![],
+                ),
+                element("m_name", AZSTD_STRING, Some(b"Wolf".to_vec()), vec![]),
+                element(
+                    "m_tags",
+                    AZSTD_VECTOR,
+                    None,
+                    (1..=3)
+                        .map(|t: i32| {
+                            element("element", INT, Some(t.to_be_bytes().to_vec()), vec![])
+                        })
+                        .collect(),
+                ),
+            ],
+        );
+        root.update_flags();
+        let stream = ObjectStream {
+            version: 3,
+            elements: vec![root],
+            ..Default::default()
+        };
+        let (original, mut stream) = roundtrip(&stream);
+        assert_eq!(
+            roundtrip(&stream).0,
+            original,
+            "unedited streams are unchanged"
+        );
+
+        assert_eq!(stream.set_value("root/m_maxHealth", "250").unwrap(), 1);
+        assert_eq!(
+            stream
+                .set_value("root/m_name", "Dire wolf of the north")
+                .unwrap(),
+            1
+        );
+        assert_eq!(stream.remove("root/m_tags/element[value!=2]").unwrap(), 2);
+        assert!(stream.set_value("root/m_tags", "1").is_err());
+        assert!(stream.set_value("root/m_maxHealth", "many").is_err());
+        // m_name comes first and takes the value, m_tags has none: neither is changed
+        assert!(stream
+            .set_value("root//*[value!=250.0000000]", "Alpha")
+            .is_err());
+
+        let (_, read) = roundtrip(&stream);
+        let value = |expr: &str| read.select(expr).unwrap()[0].element.value().unwrap();
+        assert_eq!(value("root/m_maxHealth"), "250.0000000");
+        assert_eq!(value("root/m_name"), "Dire wolf of the north");
+        assert_eq!(read.select("root/m_tags/element").unwrap().len(), 1);
+        assert_eq!(value("root/m_tags/element"), "2");
+    }
+}
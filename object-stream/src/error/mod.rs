@@ -1,32 +1,34 @@
 use std;
 use std::fmt::{self, Display};
+use std::io;
 
 use serde::{de, ser};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-// This is a bare-bones implementation. A real library would provide additional
-// information in its error type, for example the line and column at which the
-// error occurred, the byte offset into the input, or the current key being
-// processed.
 #[derive(Debug)]
 pub enum Error {
-    // One or more variants that can be created by data structures through the
-    // `ser::Error` and `de::Error` traits. For example the Serialize impl for
-    // Mutex<T> might return an error because the mutex is poisoned, or the
-    // Deserialize impl for a struct may return an error because a required
-    // field is missing.
+    // Created by data structures through the `ser::Error` and `de::Error` traits.
     Message(String),
 
-    // Zero or more variants that can be created directly by the Serializer and
-    // Deserializer without going through `ser::Error` and `de::Error`. These
-    // are specific to the format, in this case JSON.
     Eof,
     ExpectedElement,
     ExpectedValue,
     ExpectedType,
     ExpectedVersion,
-    Io(std::io::Error),
+    UnsupportedVersion(u32),
+    Io(io::Error),
+
+    /// A binary stream that couldn't be read, with where it went wrong.
+    Parse {
+        /// Byte offset of the failed read from the start of the stream.
+        offset: u64,
+        /// Flag byte of the element being read, if it got that far.
+        flags: Option<u8>,
+        /// Field names (or type names, or name CRCs) from the root to the failing element.
+        path: Vec<String>,
+        reason: String,
+    },
 }
 
 impl ser::Error for Error {
@@ -46,14 +48,55 @@ impl Display for Error {
         match self {
             Error::Message(msg) => formatter.write_str(msg),
             Error::Eof => formatter.write_str("unexpected end of input"),
-            Error::ExpectedElement => todo!(),
-            Error::ExpectedValue => todo!(),
-            Error::ExpectedType => todo!(),
-            Error::ExpectedVersion => todo!(),
+            Error::ExpectedElement => formatter.write_str("expected an element"),
+            Error::ExpectedValue => formatter.write_str("expected an element value"),
+            Error::ExpectedType => formatter.write_str("expected an element type id"),
+            Error::ExpectedVersion => formatter.write_str("expected an ObjectStream version"),
+            Error::UnsupportedVersion(version) => {
+                write!(formatter, "unsupported ObjectStream version {version}")
+            }
             Error::Io(err) => formatter.write_str(&err.to_string()),
-            /* and so forth */
+            Error::Parse {
+                offset,
+                flags,
+                path,
+                reason,
+            } => {
+                write!(formatter, "{reason} at byte {offset} ({offset:#x})")?;
+                if let Some(flags) = flags {
+                    write!(formatter, ", flags {flags:#04x}")?;
+                }
+                match path.is_empty() {
+                    true => formatter.write_str(", at the root"),
+                    false => write!(formatter, ", in {}", path.join("/")),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for Error {}
+/// Keeps `?` working in `io::Result` code, the original error can be recovered with
+/// `io::Error::get_ref` and `downcast_ref`.
+impl From<Error> for io::Error {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::Io(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
@@ -1,13 +1,33 @@
+pub mod borrowed;
+pub mod container;
+mod data;
 mod de;
+pub mod diff;
+pub mod edit;
 mod error;
+pub mod o3de;
+pub mod query;
+pub mod schema;
+pub mod scriptcanvas;
 pub mod ser;
+pub mod slice;
 mod types;
+pub mod unresolved;
+pub mod usage;
+pub mod validate;
+pub mod visit;
+
+pub use borrowed::{ElementRef, ObjectStreamRef};
+pub use de::{from_element, from_slice, Deserializer};
+pub use error::Error;
+pub use ser::{to_element, to_writer};
+pub use utils::types::braced;
 
 use crc32fast::hash;
 use serde::{self, Deserialize, Serialize};
 use serde_json::Value;
 use std::io::{self, Cursor, Read, Write};
-use utils::{lumberyard::LumberyardSource, types::uuid_data_to_serialize};
+use utils::{lumberyard::LumberyardSource, types::AzValue};
 use uuid::{self, serde::compact, Uuid};
 
 const ST_BINARYFLAG_MASK: u8 = 0xF8;
@@ -19,6 +39,11 @@ const ST_BINARYFLAG_HAS_NAME: u8 = 1 << 6;
 const ST_BINARYFLAG_HAS_VERSION: u8 = 1 << 7;
 const ST_BINARYFLAG_ELEMENT_END: u8 = 0;
 
+/// Binary layouts written by the engine: 1 is the original, 2 adds a specialization type id
+/// after every element type and 3 drops it again.
+const OBJECT_STREAM_VERSIONS: std::ops::RangeInclusive<u32> = 1..=3;
+pub(crate) const OBJECT_STREAM_VERSION: u32 = 3;
+
 const BINARY_STREAM_TAG: u8 = 0;
 const XML_STREAM_TAG: u8 = b'<';
 const JSON_STREAM_TAG: u8 = b'{';
@@ -32,6 +57,21 @@ impl StreamTag {
     pub const JSON: Self = StreamTag(JSON_STREAM_TAG);
 }
 
+impl StreamTag {
+    /// Identifies ObjectStream data by its leading bytes, text streams by their root
+    /// `ObjectStream` element.
+    pub fn detect(buf: &[u8]) -> Option<Self> {
+        let head = &buf[..buf.len().min(256)];
+        let contains = |needle: &[u8]| head.windows(needle.len()).any(|w| w == needle);
+        match buf {
+            [BINARY_STREAM_TAG, 0x00, 0x00, 0x00, 0x01..=0x03, ..] => Some(Self::BINARY),
+            [XML_STREAM_TAG, ..] if contains(b"<ObjectStream") => Some(Self::XML),
+            [JSON_STREAM_TAG, ..] if contains(b"\"ObjectStream\"") => Some(Self::JSON),
+            _ => None,
+        }
+    }
+}
+
 impl Default for StreamTag {
     fn default() -> Self {
         Self::BINARY
@@ -51,14 +91,39 @@ pub struct ObjectStream {
     elements: Vec<Element>,
 }
 
-impl From<XMLObjectStream> for ObjectStream {
-    fn from(value: XMLObjectStream) -> Self {
-        Self {
+impl TryFrom<XMLObjectStream> for ObjectStream {
+    type Error = io::Error;
+
+    fn try_from(value: XMLObjectStream) -> io::Result<Self> {
+        let mut stream = Self {
             _tag: StreamTag::BINARY,
-            version: value.version,
-            elements: value.elements.into_iter().map(Element::from).collect(),
-            ..Default::default()
-        }
+            version: check_version(value.version)?,
+            elements: value
+                .elements
+                .into_iter()
+                .map(Element::try_from)
+                .collect::<io::Result<_>>()?,
+        };
+        stream.elements.iter_mut().for_each(Element::update_flags);
+        Ok(stream)
+    }
+}
+
+impl TryFrom<JSONObjectStream> for ObjectStream {
+    type Error = io::Error;
+
+    fn try_from(value: JSONObjectStream) -> io::Result<Self> {
+        let mut stream = Self {
+            _tag: StreamTag::BINARY,
+            version: check_version(value.version)?,
+            elements: value
+                .elements
+                .into_iter()
+                .map(Element::try_from)
+                .collect::<io::Result<_>>()?,
+        };
+        stream.elements.iter_mut().for_each(Element::update_flags);
+        Ok(stream)
     }
 }
 
@@ -122,9 +187,26 @@ impl ObjectStream {
         }
         None
     }
+
+    /// Every element matching `query`, in document order, with its path from the root.
+    pub fn query_all<F>(&self, query: F) -> Vec<query::Match<'_>>
+    where
+        F: Fn(&Element) -> bool,
+    {
+        let mut matches = vec![];
+        for element in &self.elements {
+            element.collect_matches(&query, &mut vec![], &mut matches);
+        }
+        matches
+    }
+
+    /// Finds every element matching the [`query`] path expression, starting at the roots.
+    pub fn select(&self, expr: &str) -> error::Result<Vec<query::Match<'_>>> {
+        Ok(query::Query::parse(expr)?.select(&self.elements))
+    }
 }
 
-#[derive(PartialEq, Default, Debug, Serialize, Deserialize)]
+#[derive(PartialEq, Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Element {
     flags: u8,
     name_crc: Option<u32>,
@@ -140,49 +222,54 @@ pub struct Element {
     field: Option<String>,
 }
 
-impl From<XMLElement> for Element {
-    fn from(value: XMLElement) -> Self {
-        Self {
+impl TryFrom<XMLElement> for Element {
+    type Error = io::Error;
+
+    fn try_from(value: XMLElement) -> io::Result<Self> {
+        Ok(Self {
+            name_crc: value.field.as_deref().map(field_crc),
+            data: value
+                .value
+                .map(|v| AzValue::parse(&value.id, &v).map(|v| v.to_bytes()))
+                .transpose()?,
+            elements: value
+                .elements
+                .into_iter()
+                .map(Element::try_from)
+                .collect::<io::Result<_>>()?,
             id: value.id,
-            name: value.name.to_owned(),
-            name_crc: {
-                if !value.name.is_empty() {
-                    Some(hash(value.name.as_bytes()))
-                } else {
-                    None
-                }
-            },
+            name: value.name,
+            field: value.field,
             version: value.version,
-            elements: value.elements.into_iter().map(Element::from).collect(),
+            specialization: value.specialization,
             ..Default::default()
-        }
+        })
     }
 }
 
-impl From<JSONElement> for Element {
-    fn from(value: JSONElement) -> Self {
-        Self {
+impl TryFrom<JSONElement> for Element {
+    type Error = io::Error;
+
+    fn try_from(value: JSONElement) -> io::Result<Self> {
+        Ok(Self {
+            name_crc: value.field.as_deref().map(field_crc),
+            data: value
+                .value
+                .map(|v| AzValue::parse(&value.id, &v).map(|v| v.to_bytes()))
+                .transpose()?,
+            elements: value
+                .elements
+                .unwrap_or_default()
+                .into_iter()
+                .map(Element::try_from)
+                .collect::<io::Result<_>>()?,
             id: value.id,
-            name: value.name.to_owned(),
-            // flags: todo!(),
-            name_crc: {
-                if !value.name.is_empty() {
-                    Some(hash(value.name.as_bytes()))
-                } else {
-                    None
-                }
-            },
+            name: value.name,
             field: value.field,
             version: value.version,
-            // data_size: todo!(),
-            // data: todo!(),
             specialization: value.specialization,
-            elements: value
-                .elements
-                .map(|ele| ele.into_iter().map(Element::from).collect())
-                .unwrap_or_default(),
             ..Default::default()
-        }
+        })
     }
 }
 
@@ -198,6 +285,13 @@ pub struct XMLElement {
     version: Option<u8>,
     #[serde(rename = "@type", with = "uuid_braced_uppercase")]
     id: Uuid,
+    #[serde(
+        default,
+        rename = "@specializationTypeId",
+        with = "option_braced_uppercase",
+        skip_serializing_if = "Option::is_none"
+    )]
+    specialization: Option<Uuid>,
     #[serde(default, rename = "Class")]
     elements: Vec<XMLElement>,
 }
@@ -211,16 +305,15 @@ impl XMLElement {
 impl From<Element> for XMLElement {
     fn from(value: Element) -> Self {
         Self {
+            field: value.field_name(),
+            value: value
+                .data
+                .as_ref()
+                .map(|data| Value::String(AzValue::decode(&value.id, data).to_text())),
             name: value.name,
-            field: value.field,
-            value: match value.data {
-                Some(data) if !data.is_empty() || value.elements.is_empty() => {
-                    uuid_data_to_serialize(&value.id, &data, false).ok()
-                }
-                _ => None,
-            },
             version: value.version,
             id: value.id,
+            specialization: value.specialization,
             elements: value.elements.into_iter().map(XMLElement::from).collect(),
         }
     }
@@ -235,6 +328,7 @@ pub struct JSONElement {
     #[serde(rename = "typeName")]
     name: String,
     #[serde(
+        default,
         rename = "specializationTypeId",
         with = "option_braced_uppercase",
         skip_serializing_if = "Option::is_none"
@@ -251,23 +345,17 @@ pub struct JSONElement {
 impl From<Element> for JSONElement {
     fn from(value: Element) -> Self {
         Self {
-            field: value.field,
+            field: value.field_name(),
             id: value.id,
             name: value.name,
             specialization: value.specialization,
-            value: match &value.data {
-                Some(data) if !data.is_empty() => {
-                    uuid_data_to_serialize(&value.id, data, true).ok().map(|v| {
-                        if !v.is_string() {
-                            Value::String(v.to_string())
-                        } else {
-                            v
-                        }
-                    })
+            // the engine stores JSON values as strings
+            value: value.data.as_ref().map(|data| {
+                match AzValue::decode(&value.id, data).to_json() {
+                    Value::String(v) => Value::String(v),
+                    v => Value::String(v.to_string()),
                 }
-                Some(data) if data.is_empty() && value.elements.is_empty() => Some("".into()),
-                _ => None,
-            },
+            }),
             version: value.version,
             elements: {
                 let ele: Vec<JSONElement> =
@@ -283,6 +371,35 @@ impl From<Element> for JSONElement {
 }
 
 impl Element {
+    /// The decoded data, `None` for elements without data.
+    pub fn az_value(&self) -> Option<AzValue> {
+        Node::az_value(self)
+    }
+
+    /// The value as it is written in the XML format, `None` for elements without data.
+    pub fn value(&self) -> Option<String> {
+        Node::value(self)
+    }
+
+    /// Recomputes `flags` and `data_size` from the element contents the way the engine's
+    /// binary writer does: values shorter than 7 bytes store their size inline, anything
+    /// larger uses an extra 1, 2 or 4 byte size field.
+    pub(crate) fn update_flags(&mut self) {
+        let mut flags = ST_BINARYFLAG_ELEMENT_HEADER;
+        if self.name_crc.is_some() {
+            flags |= ST_BINARYFLAG_HAS_NAME;
+        }
+        if self.version.is_some() {
+            flags |= ST_BINARYFLAG_HAS_VERSION;
+        }
+        self.data_size = self.data.as_ref().map(|data| data.len());
+        if let Some(size) = self.data_size {
+            flags |= ST_BINARYFLAG_HAS_VALUE | size_flags(size);
+        }
+        self.flags = flags;
+        self.elements.iter_mut().for_each(Element::update_flags);
+    }
+
     pub fn query_elements<F>(&self, query: &F) -> Option<&Element>
     where
         F: Fn(&Element) -> bool,
@@ -299,7 +416,44 @@ impl Element {
         None
     }
 
-    fn to_writer<W>(&self, writer: &mut W) -> io::Result<()>
+    /// Every descendant (and the element itself) matching `query`, with paths relative to
+    /// this element's parent.
+    pub fn query_all<F>(&self, query: F) -> Vec<query::Match<'_>>
+    where
+        F: Fn(&Element) -> bool,
+    {
+        let mut matches = vec![];
+        self.collect_matches(&query, &mut vec![], &mut matches);
+        matches
+    }
+
+    /// Finds every descendant matching the [`query`] path expression.
+    pub fn select(&self, expr: &str) -> error::Result<Vec<query::Match<'_>>> {
+        Ok(query::Query::parse(expr)?.select(&self.elements))
+    }
+
+    fn collect_matches<'a, F>(
+        &'a self,
+        query: &F,
+        path: &mut Vec<String>,
+        matches: &mut Vec<query::Match<'a>>,
+    ) where
+        F: Fn(&Element) -> bool,
+    {
+        path.push(self.path_segment());
+        if query(self) {
+            matches.push(query::Match {
+                path: path.clone(),
+                element: self,
+            });
+        }
+        for child in &self.elements {
+            child.collect_matches(query, path, matches);
+        }
+        path.pop();
+    }
+
+    fn to_writer<W>(&self, writer: &mut W, stream_version: u32) -> io::Result<()>
     where
         W: Write,
     {
@@ -312,7 +466,8 @@ impl Element {
         }
         writer.write_all(&self.id.as_u128().to_be_bytes())?;
 
-        if let Some(specialized) = self.specialization {
+        if stream_version == 2 {
+            let specialized = self.specialization.unwrap_or(self.id);
             writer.write_all(&specialized.as_u128().to_be_bytes())?;
         }
         if self.flags & ST_BINARYFLAG_HAS_VALUE > 0 {
@@ -333,9 +488,9 @@ impl Element {
             writer.write_all(data)?;
         }
 
-        self.elements
-            .iter()
-            .for_each(|ele| ele.to_writer(writer).unwrap());
+        for ele in &self.elements {
+            ele.to_writer(writer, stream_version)?;
+        }
         writer.write_all(&[0])?;
 
         Ok(())
@@ -351,8 +506,7 @@ pub mod uuid_braced_uppercase {
     where
         S: Serializer,
     {
-        let s = uuid.as_braced().to_string().to_uppercase();
-        serializer.serialize_str(&s)
+        serializer.serialize_str(&super::braced(uuid))
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<Uuid, D::Error>
@@ -374,7 +528,7 @@ pub mod option_braced_uppercase {
         S: Serializer,
     {
         match value {
-            Some(uuid) => uuid::serde::braced::serialize(uuid, serializer),
+            Some(uuid) => serializer.serialize_str(&super::braced(uuid)),
             None => serializer.serialize_none(),
         }
     }
@@ -405,7 +559,7 @@ pub mod option_borrow_braced {
         S: Serializer,
     {
         match value {
-            Some(uuid) => serializer.serialize_str(&uuid.as_braced().to_string().to_uppercase()),
+            Some(uuid) => serializer.serialize_str(&super::braced(uuid)),
             None => serializer.serialize_none(),
         }
     }
@@ -425,38 +579,170 @@ pub mod option_borrow_braced {
     }
 }
 
-#[derive(Debug)]
-struct EOE;
+/// Read access shared by [`Element`] and [`ElementRef`], so scans can run on either tree.
+pub(crate) trait Node: Sized {
+    fn id(&self) -> &Uuid;
+    /// Empty when the type couldn't be resolved.
+    fn type_name(&self) -> &str;
+    fn name_crc(&self) -> Option<u32>;
+    fn field(&self) -> Option<&str>;
+    fn version(&self) -> Option<u8>;
+    fn data(&self) -> Option<&[u8]>;
+    fn children(&self) -> &[Self];
+
+    fn az_value(&self) -> Option<AzValue> {
+        self.data().map(|data| AzValue::decode(self.id(), data))
+    }
+
+    fn value(&self) -> Option<String> {
+        self.az_value().map(|value| value.to_text())
+    }
+
+    /// The resolved field name, or the name CRC as hex when it couldn't be resolved so the
+    /// text formats can still be converted back.
+    fn field_name(&self) -> Option<String> {
+        let crc = self.name_crc()?;
+        match self.field() {
+            Some(field) if field_crc(field) == crc => Some(field.to_owned()),
+            _ => Some(format!("0x{:08x}", crc)),
+        }
+    }
 
-impl std::fmt::Display for EOE {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "End of Elements")
+    /// Name of the element in error paths: the field name, else the type name, else the
+    /// name CRC or type id.
+    fn path_segment(&self) -> String {
+        path_segment(self.field(), self.type_name(), self.name_crc(), self.id())
     }
 }
 
-impl std::error::Error for EOE {}
+impl Node for Element {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn type_name(&self) -> &str {
+        &self.name
+    }
+
+    fn name_crc(&self) -> Option<u32> {
+        self.name_crc
+    }
+
+    fn field(&self) -> Option<&str> {
+        self.field.as_deref()
+    }
+
+    fn version(&self) -> Option<u8> {
+        self.version
+    }
+
+    fn data(&self) -> Option<&[u8]> {
+        self.data.as_deref()
+    }
+
+    fn children(&self) -> &[Self] {
+        &self.elements
+    }
+}
+
+fn path_segment(field: Option<&str>, name: &str, name_crc: Option<u32>, id: &Uuid) -> String {
+    match (field, name_crc, name.is_empty()) {
+        (Some(field), _, _) => field.to_owned(),
+        (None, _, false) => name.to_owned(),
+        (None, Some(crc), true) => format!("0x{:08x}", crc),
+        (None, None, true) => braced(id),
+    }
+}
+
+/// A test element with a resolved field name.
+#[cfg(test)]
+pub(crate) fn element(
+    field: &str,
+    id: Uuid,
+    data: Option<Vec<u8>>,
+    elements: Vec<Element>,
+) -> Element {
+    Element {
+        name_crc: Some(field_crc(field)),
+        field: Some(field.to_owned()),
+        id,
+        data,
+        elements,
+        ..Default::default()
+    }
+}
+
+/// Field names are stored as the CRC of the lowercase name, like `AZ_CRC`. Names that are
+/// already a hex CRC (`0x1a2b3c4d`) are used as-is.
+pub(crate) fn field_crc(field: &str) -> u32 {
+    field
+        .strip_prefix("0x")
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .unwrap_or_else(|| hash(field.to_lowercase().as_bytes()))
+}
+
+/// Tracks the position and element path while reading a binary stream, for errors.
+struct StreamReader<'a, R> {
+    reader: &'a mut R,
+    offset: u64,
+    flags: Option<u8>,
+    path: Vec<String>,
+}
+
+impl<R: Read> StreamReader<'_, R> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> error::Result<()> {
+        match self.reader.read_exact(buf) {
+            Ok(()) => {
+                self.offset += buf.len() as u64;
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Err(self.error(format!(
+                "unexpected end of stream reading {} bytes",
+                buf.len()
+            ))),
+            Err(e) => Err(self.error(e.to_string())),
+        }
+    }
+
+    fn error(&self, reason: impl Into<String>) -> Error {
+        Error::Parse {
+            offset: self.offset,
+            flags: self.flags,
+            path: self.path.clone(),
+            reason: reason.into(),
+        }
+    }
+}
 
 pub fn from_reader<R>(
     reader: &mut R,
     hashes: Option<&'static LumberyardSource>,
-) -> io::Result<ObjectStream>
+) -> error::Result<ObjectStream>
 where
     R: Read,
 {
+    let mut reader = StreamReader {
+        reader,
+        offset: 0,
+        flags: None,
+        path: vec![],
+    };
     let mut buf = [0; 1];
     reader.read_exact(&mut buf)?;
     let tag = u8::from_be_bytes(buf);
 
-    if tag != StreamTag::BINARY.0 {
-        return Err(io::Error::new(
-            io::ErrorKind::InvalidData,
-            "Not valid ObjectStream",
-        ));
+    match tag {
+        BINARY_STREAM_TAG => {}
+        XML_STREAM_TAG | JSON_STREAM_TAG => return from_text(tag, reader.reader),
+        _ => {
+            reader.offset = 0;
+            return Err(reader.error(format!("not an ObjectStream, unknown tag {tag:#04x}")));
+        }
     }
 
     let mut buf = [0; 4];
     reader.read_exact(&mut buf)?;
-    let version = u32::from_be_bytes(buf);
+    let version = check_version(u32::from_be_bytes(buf))?;
 
     let mut stream = ObjectStream {
         _tag: StreamTag::BINARY,
@@ -465,35 +751,60 @@ where
         ..Default::default()
     };
 
-    loop {
-        match read_element(reader, &stream, hashes) {
-            Ok(element) => stream.elements.push(element),
-            Err(e) if e.is::<EOE>() => return Ok(stream),
-            Err(e) => return Err(io::Error::new(io::ErrorKind::Other, format!("{e}"))),
-        }
+    while let Some(element) = read_element(&mut reader, version, hashes)? {
+        stream.elements.push(element);
     }
+    Ok(stream)
+}
+
+/// Text streams are parsed whole and converted to the binary model, `tag` is the first byte
+/// already taken from `reader`.
+fn from_text<R>(tag: u8, reader: &mut R) -> error::Result<ObjectStream>
+where
+    R: Read,
+{
+    let mut buf = vec![tag];
+    reader.read_to_end(&mut buf)?;
+    let text = String::from_utf8(buf).map_err(|e| Error::Message(e.to_string()))?;
+
+    let stream = match tag {
+        XML_STREAM_TAG => {
+            let stream: XMLObjectStream =
+                quick_xml::de::from_str(&text).map_err(|e| Error::Message(e.to_string()))?;
+            ObjectStream::try_from(stream)?
+        }
+        _ => {
+            let stream: JSONObjectStream =
+                serde_json::from_str(&text).map_err(|e| Error::Message(e.to_string()))?;
+            ObjectStream::try_from(stream)?
+        }
+    };
+    Ok(stream)
 }
 
+/// Reads the next element and its children, `None` at the end marker of the parent.
 fn read_element<R>(
-    reader: &mut R,
-    stream: &ObjectStream,
+    reader: &mut StreamReader<R>,
+    version: u32,
     hashes: Option<&'static LumberyardSource>,
-) -> Result<Element, Box<dyn std::error::Error>>
+) -> error::Result<Option<Element>>
 where
     R: Read,
 {
     let mut element = Element::default();
     let mut buf = [0; 16];
+    reader.flags = None;
     reader.read_exact(&mut buf[..1])?;
     let flags = buf[0];
 
     if flags == ST_BINARYFLAG_ELEMENT_END {
-        return Err(Box::new(EOE));
+        return Ok(None);
     }
+    reader.flags = Some(flags);
 
     if flags & ST_BINARYFLAG_HAS_NAME > 0 {
         reader.read_exact(&mut buf[..4])?;
-        let name_crc = u32::from_be_bytes(buf[..4].try_into()?);
+        let name_crc = u32::from_be_bytes(buf[..4].try_into().unwrap());
         element.field = hashes.and_then(|v| v.crcs.get(&name_crc).cloned());
         element.name_crc = Some(name_crc);
     }
@@ -505,14 +816,15 @@ where
     }
 
     reader.read_exact(&mut buf)?;
-    element.id = Uuid::from_slice(&buf)?;
+    element.id = Uuid::from_bytes(buf);
     element.name = hashes
         .and_then(|v| v.uuids.get(&element.id).cloned())
         .unwrap_or_default();
+    reader.path.push(element.path_segment());
 
-    if stream.version == 2 {
+    if version == 2 {
         reader.read_exact(&mut buf)?;
-        element.specialization = Some(Uuid::from_slice(&buf)?);
+        element.specialization = Some(Uuid::from_bytes(buf));
     }
 
     if flags & ST_BINARYFLAG_HAS_VALUE > 0 {
@@ -521,20 +833,19 @@ where
             match value_bytes {
                 1 => {
                     reader.read_exact(&mut buf[..1])?;
-                    Some(u8::from_le_bytes(buf[..1].try_into()?) as usize)
+                    Some(buf[0] as usize)
                 }
                 2 => {
                     reader.read_exact(&mut buf[..2])?;
-                    Some(u16::from_be_bytes(buf[..2].try_into()?) as usize)
+                    Some(u16::from_be_bytes(buf[..2].try_into().unwrap()) as usize)
                 }
                 4 => {
                     reader.read_exact(&mut buf[..4])?;
-                    Some(u32::from_be_bytes(buf[..4].try_into()?) as usize)
+                    Some(u32::from_be_bytes(buf[..4].try_into().unwrap()) as usize)
                 }
                 _ => {
-                    return Err(Box::new(io::Error::new(
-                        io::ErrorKind::Other,
-                        "Unsupported DataSize Value Byte",
+                    return Err(reader.error(format!(
+                        "unsupported extra size field of {value_bytes} bytes"
                     )))
                 }
             }
@@ -547,41 +858,56 @@ where
         let mut buf = vec![0; data_size];
         reader.read_exact(&mut buf)?;
         element.data = Some(buf);
-        if element
-            .field
-            .as_ref()
-            .is_some_and(|v| v == "m_collisionFilterOverride")
-            && element.data.as_ref().is_some_and(|v| {
-                v != &[
-                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-                ]
-                .to_vec()
-            })
-        {
-            dbg!(&element.data, &element.data_size);
-        }
     }
     element.flags = flags;
 
-    loop {
-        match read_element(reader, stream, hashes) {
-            Ok(child_element) => element.elements.push(child_element),
-            Err(e) if e.is::<EOE>() => return Ok(element),
-            Err(e) => return Err(e),
-        }
+    while let Some(child) = read_element(reader, version, hashes)? {
+        element.elements.push(child);
+    }
+    reader.path.pop();
+    Ok(Some(element))
+}
+
+/// The size bits of the flags for a value of `size` bytes, inline below 7 bytes and in the
+/// smallest extra size field otherwise.
+pub(crate) fn size_flags(size: usize) -> u8 {
+    match size {
+        size if size < ST_BINARY_VALUE_SIZE_MASK as usize => size as u8,
+        size if size < 0x100 => ST_BINARYFLAG_EXTRA_SIZE_FIELD | 1,
+        size if size < 0x10000 => ST_BINARYFLAG_EXTRA_SIZE_FIELD | 2,
+        _ => ST_BINARYFLAG_EXTRA_SIZE_FIELD | 4,
+    }
+}
+
+fn check_version(version: u32) -> error::Result<u32> {
+    match OBJECT_STREAM_VERSIONS.contains(&version) {
+        true => Ok(version),
+        false => Err(Error::UnsupportedVersion(version)),
     }
 }
 
 impl ObjectStream {
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Changes the binary layout used by [`ObjectStream::to_writer`]. Elements without a
+    /// specialization are written with their own type id when converting to version 2.
+    pub fn set_version(&mut self, version: u32) -> error::Result<()> {
+        self.version = check_version(version)?;
+        Ok(())
+    }
+
+    /// Writes the stream in the layout of its version.
     pub fn to_writer<W>(&self, writer: &mut W) -> io::Result<()>
     where
         W: Write,
     {
         writer.write_all(&0u8.to_be_bytes())?;
         writer.write_all(&self.version.to_be_bytes())?;
-        self.elements
-            .iter()
-            .for_each(|ele| ele.to_writer(writer).unwrap());
+        for ele in &self.elements {
+            ele.to_writer(writer, self.version)?;
+        }
         writer.write_all(&[0])?;
 
         Ok(())
@@ -621,6 +947,332 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn option_braced_uppercase() {
+        let id = Uuid::from_u128(0xabcdef);
+        let value =
+            super::option_braced_uppercase::serialize(&Some(id), serde_json::value::Serializer)
+                .unwrap();
+        assert_eq!(value, "{00000000-0000-0000-0000-000000ABCDEF}");
+    }
+
+    #[test]
+    fn text_roundtrip() -> io::Result<()> {
+        use utils::types::*;
+
+        let mut asset = vec![0x11; 16];
+        asset.extend_from_slice(&7u32.to_be_bytes());
+        asset.extend_from_slice(&[0; 12]);
+        asset.extend_from_slice(&[0x22; 16]);
+        asset.extend_from_slice(&12u64.to_be_bytes());
+        asset.extend_from_slice(b"a/b,hint={c}");
+
+        let mut root = Element {
+            id: Uuid::from_u128(1),
+            version: Some(2),
+            elements: vec![
+                element("m_int", INT, Some((-5i32).to_be_bytes().to_vec()), vec![]),
+                element(
+                    "m_float",
+                    FLOAT,
+                    Some(0.1f32.to_be_bytes().to_vec()),
+                    vec![],
+                ),
+                element(
+                    "m_precise",
+                    FLOAT,
+                    Some(16777217f32.to_be_bytes().to_vec()),
+                    vec![],
+                ),
+                element(
+                    "m_double",
+                    DOUBLE,
+                    Some(1e-12f64.to_be_bytes().to_vec()),
+                    vec![],
+                ),
+                element("m_bool", BOOL, Some(vec![1]), vec![]),
+                element("m_odd_bool", BOOL, Some(vec![2]), vec![]),
+                element(
+                    "m_uuid",
+                    AZ_UUID,
+                    Some((1u128 << 100).to_be_bytes().to_vec()),
+                    vec![],
+                ),
+                element("m_name", AZSTD_STRING, Some(b"Iron Ore".to_vec()), vec![]),
+                element("m_hexlike", AZSTD_STRING, Some(b"0xbeef".to_vec()), vec![]),
+                element("m_empty", AZSTD_STRING, Some(vec![]), vec![]),
+                element(
+                    "m_enum",
+                    Uuid::from_u128(2),
+                    Some(3u32.to_be_bytes().to_vec()),
+                    vec![],
+                ),
+                element("m_asset", ASSET, Some(asset), vec![]),
+                element(
+                    "m_position",
+                    VECTOR3,
+                    Some(
+                        [1f32, -0.0, 1.0 / 3.0]
+                            .iter()
+                            .flat_map(|f| f.to_be_bytes())
+                            .collect(),
+                    ),
+                    vec![],
+                ),
+                element("m_long", AZSTD_STRING, Some(vec![b'x'; 300]), vec![]),
+                Element {
+                    name_crc: Some(0x1234abcd),
+                    id: Uuid::from_u128(3),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        root.update_flags();
+        let mut original = vec![];
+        ObjectStream {
+            version: 3,
+            elements: vec![root],
+            ..Default::default()
+        }
+        .to_writer(&mut original)?;
+
+        let crcs = [
+            ("m_int", field_crc("m_int")),
+            ("m_name", field_crc("m_name")),
+        ];
+        let hashes: &'static LumberyardSource = Box::leak(Box::new(LumberyardSource {
+            uuids: Default::default(),
+            crcs: crcs
+                .iter()
+                .map(|(name, crc)| (*crc, name.to_string()))
+                .collect(),
+        }));
+
+        for hashes in [None, Some(hashes)] {
+            let stream = from_reader(&mut Cursor::new(&original), hashes)?;
+            let xml = quick_xml::se::to_string(&XMLObjectStream::from(stream))
+                .map_err(io::Error::other)?;
+            let xml: XMLObjectStream = quick_xml::de::from_str(&xml).map_err(io::Error::other)?;
+            let mut buf = vec![];
+            ObjectStream::try_from(xml)?.to_writer(&mut buf)?;
+            assert_eq!(buf, original);
+
+            let stream = from_reader(&mut Cursor::new(&original), hashes)?;
+            let json = serde_json::to_string(&JSONObjectStream::from(stream))?;
+            let json: JSONObjectStream = serde_json::from_str(&json)?;
+            let mut buf = vec![];
+            ObjectStream::try_from(json)?.to_writer(&mut buf)?;
+            assert_eq!(buf, original);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn fixture_roundtrip() -> io::Result<()> {
+        use utils::types::*;
+
+        let string =
+            |field: &str, len: usize| element(field, AZSTD_STRING, Some(vec![b'x'; len]), vec![]);
+        let mut root = Element {
+            version: Some(1),
+            ..element(
+                "root",
+                Uuid::from_u128(1),
+                None,
+                vec![
+                    // inline size, then 1, 2 and 4 byte extra size fields
+                    string("m_short", 6),
+                    string("m_byte", 200),
+                    string("m_word", 300),
+                    string("m_dword", 70_000),
+                    Element {
+                        specialization: Some(Uuid::from_u128(0x10)),
+                        ..element(
+                            "m_values",
+                            AZSTD_VECTOR,
+                            None,
+                            vec![element(
+                                "element",
+                                INT,
+                                Some(4i32.to_be_bytes().to_vec()),
+                                vec![],
+                            )],
+                        )
+                    },
+                    Element {
+                        name_crc: Some(0x1234abcd),
+                        field: None,
+                        ..element("", FLOAT, Some(1f32.to_be_bytes().to_vec()), vec![])
+                    },
+                ],
+            )
+        };
+        root.update_flags();
+        let extra_size = |element: &Element| element.flags & ST_BINARYFLAG_EXTRA_SIZE_FIELD > 0;
+        assert!(!extra_size(&root.elements[0]));
+        for (i, size) in [(1, 1), (2, 2), (3, 4)] {
+            assert!(extra_size(&root.elements[i]));
+            assert_eq!(root.elements[i].flags & ST_BINARY_VALUE_SIZE_MASK, size);
+        }
+
+        for version in OBJECT_STREAM_VERSIONS {
+            let mut original = vec![];
+            ObjectStream {
+                version,
+                elements: vec![root.clone()],
+                ..Default::default()
+            }
+            .to_writer(&mut original)?;
+
+            let stream = from_reader(&mut Cursor::new(&original), None)?;
+            let values = &stream.elements[0].elements[4];
+            match version {
+                2 => assert_eq!(values.specialization, Some(Uuid::from_u128(0x10))),
+                _ => assert_eq!(values.specialization, None),
+            }
+
+            let xml = quick_xml::se::to_string(&XMLObjectStream::from(stream))
+                .map_err(io::Error::other)?;
+            assert!(xml.contains(r#"field="0x1234abcd""#));
+            let xml: XMLObjectStream = quick_xml::de::from_str(&xml).map_err(io::Error::other)?;
+            let mut buf = vec![];
+            ObjectStream::try_from(xml)?.to_writer(&mut buf)?;
+            assert_eq!(buf, original, "binary → XML → binary, version {version}");
+
+            let stream = from_reader(&mut Cursor::new(&original), None)?;
+            let json = serde_json::to_string(&JSONObjectStream::from(stream))?;
+            assert!(json.contains(r#""field":"0x1234abcd""#));
+            let json: JSONObjectStream = serde_json::from_str(&json)?;
+            let mut buf = vec![];
+            ObjectStream::try_from(json)?.to_writer(&mut buf)?;
+            assert_eq!(buf, original, "binary → JSON → binary, version {version}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn text_tags() -> io::Result<()> {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<ObjectStream version="3">
+    <Class name="int" field="m_test" value="2" type="{72039442-EB38-4D42-A1AD-CB68F7E0EEF6}"/>
+</ObjectStream>"#;
+        let json = r#"{"name":"ObjectStream","version":3,"Objects":[{"field":"m_test","typeName":"int","typeId":"{72039442-EB38-4D42-A1AD-CB68F7E0EEF6}","value":"2"}]}"#;
+        assert_eq!(StreamTag::detect(xml.as_bytes()), Some(StreamTag::XML));
+        assert_eq!(StreamTag::detect(json.as_bytes()), Some(StreamTag::JSON));
+
+        let (mut from_xml, mut from_json) = (vec![], vec![]);
+        from_reader(&mut xml.as_bytes(), None)?.to_writer(&mut from_xml)?;
+        from_reader(&mut json.as_bytes(), None)?.to_writer(&mut from_json)?;
+        assert_eq!(from_xml, from_json);
+        assert_eq!(StreamTag::detect(&from_xml), Some(StreamTag::BINARY));
+        assert_eq!(from_slice::<i32>(&from_xml, None).ok(), Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn versions() -> io::Result<()> {
+        let mut root = Element {
+            id: Uuid::from_u128(1),
+            specialization: Some(Uuid::from_u128(2)),
+            elements: vec![Element {
+                name_crc: Some(field_crc("m_value")),
+                id: utils::types::INT,
+                data: Some(7i32.to_be_bytes().to_vec()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        root.update_flags();
+        let mut stream = ObjectStream {
+            version: 2,
+            elements: vec![root],
+            ..Default::default()
+        };
+
+        let mut v2 = vec![];
+        stream.to_writer(&mut v2)?;
+        let read = from_reader(&mut Cursor::new(&v2), None)?;
+        assert_eq!(read.version(), 2);
+        assert_eq!(read.elements[0].specialization, Some(Uuid::from_u128(2)));
+        assert_eq!(
+            read.elements[0].elements[0].specialization,
+            Some(utils::types::INT)
+        );
+        let mut buf = vec![];
+        read.to_writer(&mut buf)?;
+        assert_eq!(buf, v2);
+
+        for version in [1, 3] {
+            stream.set_version(version)?;
+            let mut original = vec![];
+            stream.to_writer(&mut original)?;
+            assert_eq!(original.len(), v2.len() - 32);
+            let mut buf = vec![];
+            from_reader(&mut Cursor::new(&original), None)?.to_writer(&mut buf)?;
+            assert_eq!(buf, original);
+        }
+
+        assert!(stream.set_version(4).is_err());
+        assert!(from_reader(&mut Cursor::new([0, 0, 0, 0, 4, 0]), None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_errors() {
+        let mut root = Element {
+            id: Uuid::from_u128(1),
+            elements: vec![Element {
+                name_crc: Some(field_crc("m_value")),
+                id: utils::types::INT,
+                data: Some(7i32.to_be_bytes().to_vec()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        root.update_flags();
+        let mut buf = vec![];
+        ObjectStream {
+            version: 3,
+            elements: vec![root],
+            ..Default::default()
+        }
+        .to_writer(&mut buf)
+        .unwrap();
+
+        let hashes: &'static LumberyardSource = Box::leak(Box::new(LumberyardSource {
+            uuids: [(Uuid::from_u128(1), "Root".to_string())].into(),
+            crcs: [(field_crc("m_value"), "m_value".to_string())].into(),
+        }));
+        // cut in the middle of the int value
+        let truncated = &buf[..5 + 17 + 1 + 4 + 16 + 2];
+        match from_reader(&mut Cursor::new(truncated), Some(hashes)) {
+            Err(Error::Parse {
+                offset,
+                flags,
+                path,
+                ..
+            }) => {
+                assert_eq!(offset, 5 + 17 + 1 + 4 + 16);
+                assert_eq!(flags, Some(0x08 | 0x40 | 0x10 | 4));
+                assert_eq!(path, ["Root", "m_value"]);
+            }
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+
+        let err = from_reader(&mut Cursor::new([0x42]), None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "not an ObjectStream, unknown tag 0x42 at byte 0 (0x0), at the root"
+        );
+        let err =
+            io::Error::from(from_reader(&mut Cursor::new([0, 0, 0, 0, 9]), None).unwrap_err());
+        assert!(matches!(
+            err.get_ref().and_then(|e| e.downcast_ref::<Error>()),
+            Some(Error::UnsupportedVersion(9))
+        ));
+    }
+
     #[test]
     fn json() -> io::Result<()> {
         let json = r#"{"name":"ObjectStream","version":3,"Objects":[]}"#;
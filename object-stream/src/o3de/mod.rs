@@ -0,0 +1,379 @@
+//! Export to the JSON serialization of Open 3D Engine, so assets can be inspected in O3DE.
+//!
+//! Classes are objects with a `$type` and their base class fields merged in, sequences are
+//! arrays, maps with string keys are objects and other maps arrays of `{"Key", "Value"}`,
+//! pairs are two item arrays. Math types are float arrays, except transforms which are split
+//! into translation, rotation quaternion and uniform scale. The stream doesn't record which
+//! fields are polymorphic so every class gets a `$type`, O3DE skips it where it isn't needed.
+//!
+//! Slices become prefabs with the same entities and components. Components are written the way
+//! they are stored, runtime components aren't converted to their editor counterparts.
+
+use crate::{
+    braced,
+    container::Container,
+    slice::{Entity, Slice},
+    Element, ObjectStream,
+};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use utils::types::{AzValue, AZSTD_PAIR};
+
+impl ObjectStream {
+    /// The root element as a `JsonSerialization` document.
+    pub fn to_o3de(&self) -> Value {
+        let (name, data) = match self.elements.first() {
+            Some(root) => (type_name(root), class(root, false)),
+            None => (String::new(), Value::Object(Map::new())),
+        };
+        json!({
+            "Type": "JsonSerialization",
+            "Version": 1,
+            "ClassName": name,
+            "ClassData": data,
+        })
+    }
+}
+
+impl Slice<'_> {
+    /// The entities of the slice as a prefab named `name`.
+    pub fn to_prefab(&self, name: &str) -> Value {
+        prefab(name, &self.entities)
+    }
+}
+
+/// A prefab named `name` holding `entities`, keyed `Entity_[id]` with their components keyed
+/// `Component_[id]`.
+pub fn prefab(name: &str, entities: &[Entity<'_>]) -> Value {
+    let entities = entities
+        .iter()
+        .enumerate()
+        .map(|(i, entity)| {
+            let alias = format!("Entity_[{}]", entity.id.map_or(i as u64, |id| id.0));
+            let components = entity
+                .components
+                .iter()
+                .enumerate()
+                .map(|(i, component)| {
+                    let alias = format!("Component_[{}]", component.id.unwrap_or(i as u64));
+                    (alias, to_o3de(component.element))
+                })
+                .collect::<Map<_, _>>();
+            let entity = json!({
+                "Id": alias,
+                "Name": entity.name,
+                "Components": components,
+            });
+            (alias, entity)
+        })
+        .collect::<Map<_, _>>();
+    json!({
+        "ContainerEntity": {
+            "Id": "ContainerEntity",
+            "Name": name,
+            "Components": {},
+        },
+        "Entities": entities,
+    })
+}
+
+/// One element in the O3DE format, classes with their `$type`.
+pub fn to_o3de(element: &Element) -> Value {
+    if let Some(container) = element.container() {
+        return match container {
+            Container::Sequence(items) => items.iter().map(to_o3de).collect(),
+            Container::Optional(value) => value.map_or(Value::Null, to_o3de),
+            Container::Map(pairs) => map(&pairs),
+        };
+    }
+    if let Some((first, second)) = pair(element) {
+        return json!([to_o3de(first), to_o3de(second)]);
+    }
+    match (element.az_value(), element.elements.is_empty()) {
+        (Some(value), true) => self::value(&value),
+        _ => class(element, true),
+    }
+}
+
+fn pair(element: &Element) -> Option<(&Element, &Element)> {
+    let is_pair = element.id == AZSTD_PAIR || element.name.starts_with("AZStd::pair");
+    match is_pair && element.data.is_none() {
+        true => element.pair(),
+        false => None,
+    }
+}
+
+fn map(pairs: &[(&Element, &Element)]) -> Value {
+    let keys = pairs
+        .iter()
+        .map(|(key, _)| match key.az_value() {
+            Some(AzValue::String(name)) if key.elements.is_empty() => Some(name),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .filter(|keys| keys.iter().collect::<HashSet<_>>().len() == keys.len());
+    match keys {
+        Some(keys) => keys
+            .into_iter()
+            .zip(pairs)
+            .map(|(key, (_, value))| (key, to_o3de(value)))
+            .collect::<Map<_, _>>()
+            .into(),
+        _ => pairs
+            .iter()
+            .map(|(key, value)| json!({"Key": to_o3de(key), "Value": to_o3de(value)}))
+            .collect(),
+    }
+}
+
+fn class(element: &Element, with_type: bool) -> Value {
+    let mut object = Map::new();
+    if with_type {
+        object.insert("$type".into(), type_name(element).into());
+    }
+    if let Some(value) = element.az_value() {
+        object.insert("$value".into(), self::value(&value));
+    }
+
+    let mut members = vec![];
+    crate::data::members_of(element, &mut members);
+    let mut counts = HashMap::<&str, usize>::new();
+    for (field, _) in &members {
+        *counts.entry(field).or_default() += 1;
+    }
+    for (field, child) in &members {
+        let value = to_o3de(child);
+        if counts[field.as_str()] == 1 {
+            object.insert(field.to_owned(), value);
+            continue;
+        }
+        if let Value::Array(values) = object
+            .entry(field.to_owned())
+            .or_insert_with(|| Value::Array(vec![]))
+        {
+            values.push(value);
+        }
+    }
+    Value::Object(object)
+}
+
+fn value(value: &AzValue) -> Value {
+    match value {
+        AzValue::Uuid(id) => braced(id).into(),
+        AzValue::Crc32(crc) => (*crc).into(),
+        AzValue::Asset(asset) => json!({
+            "assetId": {
+                "guid": braced(&asset.guid),
+                "subId": asset.sub_id,
+            },
+            "assetHint": asset.hint,
+        }),
+        AzValue::Transform(m) => transform(m),
+        value => value.to_json(),
+    }
+}
+
+/// A 3x4 row major transform as translation, rotation quaternion and uniform scale.
+fn transform(m: &[f32; 12]) -> Value {
+    let float = |f: f32| AzValue::F32(f).to_json();
+    let r = |row: usize, column: usize| m[row * 4 + column];
+    let scale = (r(0, 0).powi(2) + r(1, 0).powi(2) + r(2, 0).powi(2)).sqrt();
+    let rotation = match scale > 0.0 {
+        true => quaternion(|row, column| r(row, column) / scale),
+        false => [0.0, 0.0, 0.0, 1.0],
+    };
+    json!({
+        "Translation": [float(m[3]), float(m[7]), float(m[11])],
+        "Rotation": rotation.map(float),
+        "Scale": float(scale),
+    })
+}
+
+/// `[x, y, z, w]` of the rotation matrix `r`.
+fn quaternion(r: impl Fn(usize, usize) -> f32) -> [f32; 4] {
+    let trace = r(0, 0) + r(1, 1) + r(2, 2);
+    if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        [
+            (r(2, 1) - r(1, 2)) / s,
+            (r(0, 2) - r(2, 0)) / s,
+            (r(1, 0) - r(0, 1)) / s,
+            0.25 * s,
+        ]
+    } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+        let s = (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * 2.0;
+        [
+            0.25 * s,
+            (r(0, 1) + r(1, 0)) / s,
+            (r(0, 2) + r(2, 0)) / s,
+            (r(2, 1) - r(1, 2)) / s,
+        ]
+    } else if r(1, 1) > r(2, 2) {
+        let s = (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * 2.0;
+        [
+            (r(0, 1) + r(1, 0)) / s,
+            0.25 * s,
+            (r(1, 2) + r(2, 1)) / s,
+            (r(0, 2) - r(2, 0)) / s,
+        ]
+    } else {
+        let s = (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * 2.0;
+        [
+            (r(0, 2) + r(2, 0)) / s,
+            (r(1, 2) + r(2, 1)) / s,
+            0.25 * s,
+            (r(1, 0) - r(0, 1)) / s,
+        ]
+    }
+}
+
+/// The resolved type name, or the braced type id.
+fn type_name(element: &Element) -> String {
+    match element.name.is_empty() {
+        true => braced(&element.id),
+        false => element.name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        element,
+        slice::{ENTITY, ENTITY_ID, SLICE_COMPONENT},
+    };
+    use utils::types::{AZSTD_STRING, AZSTD_UNORDERED_MAP, AZSTD_VECTOR, AZ_U64, INT, TRANSFORM};
+    use uuid::Uuid;
+
+    fn class(field: &str, name: &str, elements: Vec<Element>) -> Element {
+        Element {
+            name: name.to_owned(),
+            ..element(field, Uuid::from_u128(name.len() as u128), None, elements)
+        }
+    }
+
+    fn int(field: &str, value: i32) -> Element {
+        element(field, INT, Some(value.to_be_bytes().to_vec()), vec![])
+    }
+
+    fn u64(field: &str, value: u64) -> Element {
+        element(field, AZ_U64, Some(value.to_be_bytes().to_vec()), vec![])
+    }
+
+    fn string(field: &str, value: &str) -> Element {
+        element(field, AZSTD_STRING, Some(value.into()), vec![])
+    }
+
+    fn pair(key: Element, value: Element) -> Element {
+        element("element", AZSTD_PAIR, None, vec![key, value])
+    }
+
+    #[test]
+    fn o3de() {
+        // rotated 90 degrees around z, scaled by 2
+        let tm = [0., -2., 0., 1., 2., 0., 0., 2., 0., 0., 2., 3.];
+        let root = class(
+            "",
+            "Root",
+            vec![
+                class("BaseClass1", "Base", vec![int("m_base", 5)]),
+                element(
+                    "m_tags",
+                    AZSTD_VECTOR,
+                    None,
+                    vec![int("element", 1), int("element", 2)],
+                ),
+                element(
+                    "m_lookup",
+                    AZSTD_UNORDERED_MAP,
+                    None,
+                    vec![pair(string("value1", "a"), int("value2", 1))],
+                ),
+                element(
+                    "m_ids",
+                    AZSTD_UNORDERED_MAP,
+                    None,
+                    vec![pair(int("value1", 3), class("value2", "Child", vec![]))],
+                ),
+                element(
+                    "m_transform",
+                    TRANSFORM,
+                    Some(tm.iter().flat_map(|f: &f32| f.to_be_bytes()).collect()),
+                    vec![],
+                ),
+            ],
+        );
+        let stream = ObjectStream {
+            version: 3,
+            elements: vec![root],
+            ..Default::default()
+        };
+
+        let mut document = stream.to_o3de();
+        let transform = document["ClassData"]["m_transform"].take();
+        assert_eq!(
+            document,
+            json!({
+                "Type": "JsonSerialization",
+                "Version": 1,
+                "ClassName": "Root",
+                "ClassData": {
+                    "m_base": 5,
+                    "m_tags": [1, 2],
+                    "m_lookup": {"a": 1},
+                    "m_ids": [{"Key": 3, "Value": {"$type": "Child"}}],
+                    "m_transform": null,
+                },
+            })
+        );
+        assert_eq!(transform["Translation"], json!([1.0, 2.0, 3.0]));
+        assert_eq!(transform["Scale"], json!(2.0));
+        let rotation = transform["Rotation"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f.as_f64().unwrap())
+            .collect::<Vec<_>>();
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+        for (f, expected) in rotation.iter().zip([0., 0., half, half]) {
+            assert!((f - expected).abs() < 1e-6, "{rotation:?}");
+        }
+    }
+
+    #[test]
+    fn prefab() {
+        let component = class(
+            "element",
+            "MeshComponent",
+            vec![class("BaseClass1", "Component", vec![u64("Id", 7)])],
+        );
+        let entity = element(
+            "element",
+            ENTITY,
+            None,
+            vec![
+                element("Id", ENTITY_ID, None, vec![u64("id", 42)]),
+                string("Name", "Tree"),
+                element("Components", AZSTD_VECTOR, None, vec![component]),
+            ],
+        );
+        let slice = element(
+            "",
+            SLICE_COMPONENT,
+            None,
+            vec![element("Entities", AZSTD_VECTOR, None, vec![entity])],
+        );
+        let prefab = Slice::from_element(&slice).unwrap().to_prefab("camp");
+        assert_eq!(prefab["ContainerEntity"]["Name"], "camp");
+        assert_eq!(
+            prefab["Entities"]["Entity_[42]"],
+            json!({
+                "Id": "Entity_[42]",
+                "Name": "Tree",
+                "Components": {
+                    "Component_[7]": {"$type": "MeshComponent", "Id": 7},
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! Path expressions over element trees.
+//!
+//! Steps are separated by `/` (children) or `//` (descendants at any depth). A step is `*`, a
+//! field or type name, a name CRC (`0x1a2b3c4d`) or a braced type UUID, followed by any number
+//! of `[...]` predicates comparing the element's own value (`[value=5]`) or the value of a
+//! child (`[m_name~=Iron]`) with `=`, `!=` or `~=` (contains). Predicate values can be quoted.
+//!
+//! `//TransformComponent//m_transform` finds every `m_transform` under every
+//! `TransformComponent`.
+
+use crate::{error::Error, error::Result, field_crc, Element, ElementRef, Node};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// An element matched by a query, with the path of element names leading to it.
+#[derive(Debug)]
+pub struct Match<'a, E = Element> {
+    pub path: Vec<String>,
+    pub element: &'a E,
+}
+
+#[derive(Debug, Clone)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    descendant: bool,
+    test: Test,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Debug, Clone)]
+enum Test {
+    Any,
+    Name(String, u32),
+    Type(Uuid),
+}
+
+#[derive(Debug, Clone)]
+struct Predicate {
+    /// `None` tests the value of the element itself.
+    child: Option<Test>,
+    op: Op,
+    value: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Contains,
+}
+
+impl Query {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::Message(format!("invalid query `{expr}`: {reason}"));
+        let mut steps = vec![];
+        let mut rest = expr.trim();
+        if rest.is_empty() {
+            return Err(invalid("empty expression"));
+        }
+
+        while !rest.is_empty() {
+            let descendant = match rest.strip_prefix("//") {
+                Some(r) => {
+                    rest = r;
+                    true
+                }
+                None => {
+                    rest = rest.strip_prefix('/').unwrap_or(rest);
+                    false
+                }
+            };
+
+            let end = rest.find(['/', '[']).unwrap_or(rest.len());
+            let test = Test::parse(&rest[..end]).ok_or_else(|| invalid("missing step"))?;
+            rest = &rest[end..];
+
+            let mut predicates = vec![];
+            while let Some(r) = rest.strip_prefix('[') {
+                let end = closing_bracket(r).ok_or_else(|| invalid("unclosed `[`"))?;
+                predicates.push(
+                    Predicate::parse(&r[..end]).ok_or_else(|| invalid("malformed predicate"))?,
+                );
+                rest = &r[end + 1..];
+            }
+            if !rest.is_empty() && !rest.starts_with('/') {
+                return Err(invalid("expected `/` after predicate"));
+            }
+
+            steps.push(Step {
+                descendant,
+                test,
+                predicates,
+            });
+        }
+        Ok(Self { steps })
+    }
+
+    /// Matches the query against the children of `roots`, in document order.
+    pub fn select<'a>(&self, roots: &'a [Element]) -> Vec<Match<'a>> {
+        self.matches(roots)
+    }
+
+    /// [`Query::select`] over a borrowed stream.
+    pub fn select_ref<'a, 'b>(
+        &self,
+        roots: &'a [ElementRef<'b>],
+    ) -> Vec<Match<'a, ElementRef<'b>>> {
+        self.matches(roots)
+    }
+
+    fn matches<'a, E: Node>(&self, roots: &'a [E]) -> Vec<Match<'a, E>> {
+        let mut contexts = vec![(vec![], roots)];
+        let mut matches = vec![];
+
+        for step in &self.steps {
+            matches.clear();
+            let mut seen = HashSet::new();
+            for (path, children) in &contexts {
+                step.visit(children, path, &mut seen, &mut matches);
+            }
+            contexts = matches
+                .iter()
+                .map(|m: &Match<'a, E>| (m.path.clone(), m.element.children()))
+                .collect();
+        }
+        matches
+    }
+}
+
+impl Step {
+    fn visit<'a, E: Node>(
+        &self,
+        children: &'a [E],
+        path: &[String],
+        seen: &mut HashSet<*const E>,
+        matches: &mut Vec<Match<'a, E>>,
+    ) {
+        for child in children {
+            let mut child_path = path.to_vec();
+            child_path.push(child.path_segment());
+            if self.test.matches(child)
+                && self.predicates.iter().all(|p| p.matches(child))
+                && seen.insert(child)
+            {
+                matches.push(Match {
+                    path: child_path.clone(),
+                    element: child,
+                });
+            }
+            if self.descendant {
+                self.visit(child.children(), &child_path, seen, matches);
+            }
+        }
+    }
+}
+
+impl Test {
+    fn parse(test: &str) -> Option<Self> {
+        let test = test.trim();
+        match test {
+            "" => None,
+            "*" => Some(Test::Any),
+            _ if test.starts_with('{') => Uuid::parse_str(test).ok().map(Test::Type),
+            _ => Some(Test::Name(test.to_owned(), field_crc(test))),
+        }
+    }
+
+    fn matches(&self, element: &impl Node) -> bool {
+        match self {
+            Test::Any => true,
+            Test::Name(name, crc) => {
+                element.name_crc() == Some(*crc)
+                    || element.field() == Some(name)
+                    || element.type_name() == name
+            }
+            Test::Type(id) => element.id() == id,
+        }
+    }
+}
+
+impl Predicate {
+    fn parse(predicate: &str) -> Option<Self> {
+        let (index, op, len) = [("!=", Op::Ne), ("~=", Op::Contains), ("=", Op::Eq)]
+            .into_iter()
+            .filter_map(|(token, op)| unquoted_find(predicate, token).map(|i| (i, op, token.len())))
+            .min_by_key(|(index, _, _)| *index)?;
+
+        let child = match predicate[..index].trim() {
+            "value" => None,
+            key => Some(Test::parse(key)?),
+        };
+        let value = predicate[index + len..].trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+            .unwrap_or(value);
+
+        Some(Self {
+            child,
+            op,
+            value: value.to_owned(),
+        })
+    }
+
+    fn matches(&self, element: &impl Node) -> bool {
+        let test = |value: Option<String>| {
+            let Some(value) = value else {
+                return matches!(self.op, Op::Ne);
+            };
+            match self.op {
+                Op::Eq => value == self.value,
+                Op::Ne => value != self.value,
+                Op::Contains => value.contains(&self.value),
+            }
+        };
+        match &self.child {
+            None => test(element.value()),
+            Some(child) => match self.op {
+                // no child may have the value
+                Op::Ne => element
+                    .children()
+                    .iter()
+                    .filter(|e| child.matches(*e))
+                    .all(|e| test(e.value())),
+                _ => element
+                    .children()
+                    .iter()
+                    .filter(|e| child.matches(*e))
+                    .any(|e| test(e.value())),
+            },
+        }
+    }
+}
+
+fn unquoted_find(haystack: &str, needle: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in haystack.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, _) if haystack[index..].starts_with(needle) => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn closing_bracket(rest: &str) -> Option<usize> {
+    unquoted_find(rest, "]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element;
+    use utils::types::{AZSTD_STRING, INT};
+
+    fn entity(name: &str, x: i32) -> Element {
+        let transform = Element {
+            name: "TransformComponent".into(),
+            ..element(
+                "element",
+                Uuid::from_u128(2),
+                None,
+                vec![element(
+                    "m_transform",
+                    INT,
+                    Some(x.to_be_bytes().to_vec()),
+                    vec![],
+                )],
+            )
+        };
+        element(
+            "element",
+            Uuid::from_u128(1),
+            None,
+            vec![
+                element(
+                    "m_name",
+                    AZSTD_STRING,
+                    Some(name.as_bytes().to_vec()),
+                    vec![],
+                ),
+                element("Components", Uuid::nil(), None, vec![transform]),
+            ],
+        )
+    }
+
+    #[test]
+    fn select() {
+        let roots = vec![element(
+            "Entities",
+            Uuid::nil(),
+            None,
+            vec![entity("Tree", 1), entity("Rock/Big", 2)],
+        )];
+        let values = |expr: &str| {
+            Query::parse(expr)
+                .unwrap()
+                .select(&roots)
+                .iter()
+                .map(|m| m.element.value().unwrap_or_default())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(values("//TransformComponent//m_transform"), ["1", "2"]);
+        assert_eq!(values("//m_transform"), ["1", "2"]);
+        assert_eq!(values("Entities/*/m_name"), ["Tree", "Rock/Big"]);
+        assert_eq!(
+            values("//{00000000-0000-0000-0000-000000000002}/m_transform"),
+            ["1", "2"]
+        );
+        assert_eq!(values("//*[m_name=\"Rock/Big\"]//m_transform"), ["2"]);
+        assert_eq!(values("//*[m_name!=Tree]/m_name"), ["Rock/Big"]);
+        assert_eq!(values("//m_name[value~=ee]"), ["Tree"]);
+        assert_eq!(
+            values(&format!("//0x{:08x}", field_crc("m_transform"))),
+            ["1", "2"]
+        );
+        assert!(values("//m_missing").is_empty());
+
+        let matches = Query::parse("//m_transform").unwrap().select(&roots);
+        assert_eq!(
+            matches[1].path,
+            [
+                "Entities",
+                "element",
+                "Components",
+                "element",
+                "m_transform"
+            ]
+        );
+
+        // the borrowed tree matches the same elements
+        let mut stream = crate::ObjectStream {
+            version: 3,
+            elements: roots.clone(),
+            ..Default::default()
+        };
+        stream.elements.iter_mut().for_each(Element::update_flags);
+        let mut buf = vec![];
+        stream.to_writer(&mut buf).unwrap();
+        let borrowed = crate::ObjectStreamRef::parse(&buf, None).unwrap();
+        let matches = borrowed
+            .select("//*[m_name=\"Rock/Big\"]//m_transform")
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].element.value().as_deref(), Some("2"));
+
+        assert!(Query::parse("").is_err());
+        assert!(Query::parse("//a[m_name=1").is_err());
+        assert!(Query::parse("//a[oops]").is_err());
+    }
+}
@@ -0,0 +1,536 @@
+//! JSON Schemas of the reflected classes, built from the instances seen in streams.
+//!
+//! The schemas describe the data only format of [`ObjectStream::to_data`]: a class is an
+//! object of its fields (base classes merged), containers are arrays, objects and null, and
+//! fields of other classes refer to the schema of that class by `{TypeId}.schema.json`.
+
+use crate::{
+    braced,
+    container::{container_of, Container},
+    data::members_of,
+    Node, ObjectStream, ObjectStreamRef,
+};
+use schemars::{
+    schema::{
+        ArrayValidation, InstanceType, Metadata, ObjectValidation, RootSchema, Schema,
+        SchemaObject, SingleOrVec, SubschemaValidation,
+    },
+    Map,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    mem,
+};
+use utils::types::AzValue;
+use uuid::Uuid;
+
+/// What a value looked like in one instance.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Shape {
+    Null,
+    Integer,
+    Number,
+    Boolean,
+    String,
+    Asset,
+    /// Vectors, quaternions, matrices... as an array of this many numbers.
+    Floats(usize),
+    Class(Uuid),
+    Sequence(BTreeSet<Shape>),
+    /// Map with string or number keys, with the shapes of the values.
+    Map(BTreeSet<Shape>),
+    /// Map written as `[key, value]` arrays.
+    Pairs(BTreeSet<Shape>, BTreeSet<Shape>),
+    /// A field name used several times in one instance, collected into an array.
+    Repeated(BTreeSet<Shape>),
+}
+
+#[derive(Debug, Default)]
+struct Field {
+    shapes: BTreeSet<Shape>,
+    type_names: BTreeSet<String>,
+    /// Number of instances with this field.
+    instances: usize,
+}
+
+#[derive(Debug, Default)]
+struct Class {
+    name: String,
+    instances: usize,
+    versions: BTreeSet<u8>,
+    fields: Map<String, Field>,
+}
+
+/// Aggregates the fields, types and versions of every class over any number of streams.
+#[derive(Debug, Default)]
+pub struct SchemaBuilder {
+    classes: BTreeMap<Uuid, Class>,
+}
+
+impl SchemaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, stream: &ObjectStream) {
+        for element in &stream.elements {
+            self.shape(element);
+        }
+    }
+
+    /// [`SchemaBuilder::add`] for a borrowed stream, nothing is copied.
+    pub fn add_ref(&mut self, stream: &ObjectStreamRef<'_>) {
+        for element in stream.elements() {
+            self.shape(element);
+        }
+    }
+
+    /// Adds the classes of `other`, for builders filled in parallel.
+    pub fn merge(&mut self, other: SchemaBuilder) {
+        for (id, other) in other.classes {
+            let class = self.classes.entry(id).or_default();
+            if class.name.is_empty() {
+                class.name = other.name;
+            }
+            class.instances += other.instances;
+            class.versions.extend(other.versions);
+            for (name, other) in other.fields {
+                let field = class.fields.entry(name).or_default();
+                other
+                    .shapes
+                    .into_iter()
+                    .for_each(|shape| insert(&mut field.shapes, shape));
+                field.type_names.extend(other.type_names);
+                field.instances += other.instances;
+            }
+        }
+    }
+
+    /// Type ids of the classes seen, in order.
+    pub fn classes(&self) -> impl Iterator<Item = &Uuid> {
+        self.classes.keys()
+    }
+
+    /// File name the schemas refer to each other by.
+    pub fn file_name(id: &Uuid) -> String {
+        format!("{}.schema.json", braced(id))
+    }
+
+    pub fn schema(&self, id: &Uuid) -> Option<RootSchema> {
+        let class = self.classes.get(id)?;
+        let versions = class
+            .versions
+            .iter()
+            .map(u8::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut description = format!("{}, {} instances", braced(id), class.instances);
+        if !versions.is_empty() {
+            description.push_str(&format!(", versions {versions}"));
+        }
+
+        let properties = class
+            .fields
+            .iter()
+            .map(|(name, field)| {
+                let mut schema = any_of(&field.shapes);
+                let type_names = field.type_names.iter().cloned().collect::<Vec<_>>();
+                if !type_names.is_empty() {
+                    schema = Schema::Object(SchemaObject {
+                        metadata: Some(Box::new(Metadata {
+                            description: Some(type_names.join(" | ")),
+                            ..Default::default()
+                        })),
+                        ..into_object(schema)
+                    });
+                }
+                (name.clone(), schema)
+            })
+            .collect();
+        let required = class
+            .fields
+            .iter()
+            .filter(|(_, field)| field.instances == class.instances)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        Some(RootSchema {
+            meta_schema: Some("http://json-schema.org/draft-07/schema#".into()),
+            schema: SchemaObject {
+                metadata: Some(Box::new(Metadata {
+                    id: Some(Self::file_name(id)),
+                    title: Some(match class.name.is_empty() {
+                        true => braced(id),
+                        false => class.name.clone(),
+                    }),
+                    description: Some(description),
+                    ..Default::default()
+                })),
+                instance_type: Some(InstanceType::Object.into()),
+                object: Some(Box::new(ObjectValidation {
+                    properties,
+                    required,
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+            definitions: Map::new(),
+        })
+    }
+
+    /// CSV table of the classes: type id, name, instances, versions and fields, where fields
+    /// missing from some instances end with `?`.
+    pub fn summary(&self) -> String {
+        let mut csv = String::from("typeId,name,instances,versions,fields\n");
+        for (id, class) in &self.classes {
+            let versions = class
+                .versions
+                .iter()
+                .map(u8::to_string)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let fields = class
+                .fields
+                .iter()
+                .map(|(name, field)| match field.instances == class.instances {
+                    true => name.clone(),
+                    false => format!("{name}?"),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                braced(id),
+                quote(&class.name),
+                class.instances,
+                versions,
+                quote(&fields)
+            ));
+        }
+        csv
+    }
+
+    /// The shape of `element` in the data format, recording the classes in it.
+    fn shape<E: Node>(&mut self, element: &E) -> Shape {
+        if let Some(container) = container_of(element) {
+            return match container {
+                Container::Sequence(items) => {
+                    let mut shapes = BTreeSet::new();
+                    for item in items {
+                        insert(&mut shapes, self.shape(item));
+                    }
+                    Shape::Sequence(shapes)
+                }
+                Container::Optional(value) => match value {
+                    Some(value) => self.shape(value),
+                    None => Shape::Null,
+                },
+                Container::Map(pairs) => match crate::container::unique_keys(&pairs) {
+                    Some(_) => {
+                        let mut values = BTreeSet::new();
+                        for (_, value) in pairs {
+                            insert(&mut values, self.shape(value));
+                        }
+                        Shape::Map(values)
+                    }
+                    None => {
+                        let (mut keys, mut values) = (BTreeSet::new(), BTreeSet::new());
+                        for (key, value) in pairs {
+                            insert(&mut keys, self.shape(key));
+                            insert(&mut values, self.shape(value));
+                        }
+                        Shape::Pairs(keys, values)
+                    }
+                },
+            };
+        }
+
+        let value = element.az_value();
+        if element.children().is_empty() {
+            if let Some(value) = value {
+                return value_shape(&value);
+            }
+        }
+        self.class(element, value.as_ref());
+        Shape::Class(*element.id())
+    }
+
+    fn class<E: Node>(&mut self, element: &E, value: Option<&AzValue>) {
+        let mut members = vec![];
+        members_of(element, &mut members);
+        let mut fields = Map::<String, (BTreeSet<Shape>, BTreeSet<String>, usize)>::new();
+        if let Some(value) = value {
+            let entry = fields.entry("$value".into()).or_default();
+            insert(&mut entry.0, value_shape(value));
+            entry.2 += 1;
+        }
+        for (name, child) in members {
+            let shape = self.shape(child);
+            let entry = fields.entry(name).or_default();
+            insert(&mut entry.0, shape);
+            if !child.type_name().is_empty() {
+                entry.1.insert(child.type_name().to_owned());
+            }
+            entry.2 += 1;
+        }
+
+        let class = self.classes.entry(*element.id()).or_default();
+        if class.name.is_empty() {
+            class.name = element.type_name().to_owned();
+        }
+        class.instances += 1;
+        class.versions.extend(element.version());
+        let counts = fields
+            .iter()
+            .map(|(name, (_, _, count))| (name.clone(), *count))
+            .collect::<HashMap<_, _>>();
+        for (name, (shapes, type_names, _)) in fields {
+            let field = class.fields.entry(name.clone()).or_default();
+            match counts[&name] {
+                1 => shapes
+                    .into_iter()
+                    .for_each(|s| insert(&mut field.shapes, s)),
+                _ => insert(&mut field.shapes, Shape::Repeated(shapes)),
+            }
+            field.type_names.extend(type_names);
+            field.instances += 1;
+        }
+    }
+}
+
+/// Adds `shape` to `shapes`, merging containers of the same kind so an empty vector and a
+/// vector of numbers are one array schema.
+fn insert(shapes: &mut BTreeSet<Shape>, shape: Shape) {
+    let same = shapes
+        .iter()
+        .find(|other| {
+            !matches!(other, Shape::Floats(_) | Shape::Class(_))
+                && mem::discriminant(*other) == mem::discriminant(&shape)
+        })
+        .cloned();
+    let Some(same) = same else {
+        shapes.insert(shape);
+        return;
+    };
+    shapes.remove(&same);
+    let union = |mut a: BTreeSet<Shape>, b: BTreeSet<Shape>| {
+        b.into_iter().for_each(|shape| insert(&mut a, shape));
+        a
+    };
+    let merged = match (same, shape) {
+        (Shape::Sequence(a), Shape::Sequence(b)) => Shape::Sequence(union(a, b)),
+        (Shape::Map(a), Shape::Map(b)) => Shape::Map(union(a, b)),
+        (Shape::Repeated(a), Shape::Repeated(b)) => Shape::Repeated(union(a, b)),
+        (Shape::Pairs(k, v), Shape::Pairs(k2, v2)) => Shape::Pairs(union(k, k2), union(v, v2)),
+        (same, _) => same,
+    };
+    shapes.insert(merged);
+}
+
+fn value_shape(value: &AzValue) -> Shape {
+    match value {
+        AzValue::I8(_)
+        | AzValue::I16(_)
+        | AzValue::I32(_)
+        | AzValue::I64(_)
+        | AzValue::U8(_)
+        | AzValue::U16(_)
+        | AzValue::U32(_)
+        | AzValue::U64(_) => Shape::Integer,
+        AzValue::F32(_) | AzValue::F64(_) => Shape::Number,
+        AzValue::Bool(_) => Shape::Boolean,
+        AzValue::Asset(_) => Shape::Asset,
+        AzValue::Uuid(_) | AzValue::Crc32(_) | AzValue::String(_) | AzValue::Bytes(_) => {
+            Shape::String
+        }
+        floats => Shape::Floats(floats.floats().map(<[f32]>::len).unwrap_or_default()),
+    }
+}
+
+fn typed(instance_type: InstanceType) -> SchemaObject {
+    SchemaObject {
+        instance_type: Some(instance_type.into()),
+        ..Default::default()
+    }
+}
+
+fn array(items: Option<SingleOrVec<Schema>>, len: Option<u32>) -> SchemaObject {
+    SchemaObject {
+        array: Some(Box::new(ArrayValidation {
+            items,
+            min_items: len,
+            max_items: len,
+            ..Default::default()
+        })),
+        ..typed(InstanceType::Array)
+    }
+}
+
+fn items(shapes: &BTreeSet<Shape>) -> Option<SingleOrVec<Schema>> {
+    match shapes.is_empty() {
+        true => None,
+        false => Some(SingleOrVec::Single(Box::new(any_of(shapes)))),
+    }
+}
+
+fn shape_schema(shape: &Shape) -> Schema {
+    let schema = match shape {
+        Shape::Null => typed(InstanceType::Null),
+        Shape::Integer => typed(InstanceType::Integer),
+        Shape::Number => typed(InstanceType::Number),
+        Shape::Boolean => typed(InstanceType::Boolean),
+        Shape::String => typed(InstanceType::String),
+        Shape::Asset => SchemaObject {
+            object: Some(Box::new(ObjectValidation {
+                properties: ["assetId", "type", "hint"]
+                    .into_iter()
+                    .map(|key| (key.to_owned(), Schema::Bool(true)))
+                    .collect(),
+                required: ["assetId", "type", "hint"].map(String::from).into(),
+                ..Default::default()
+            })),
+            ..typed(InstanceType::Object)
+        },
+        Shape::Floats(len) => array(
+            Some(SingleOrVec::Single(Box::new(
+                typed(InstanceType::Number).into(),
+            ))),
+            Some(*len as u32),
+        ),
+        Shape::Class(id) => SchemaObject::new_ref(SchemaBuilder::file_name(id)),
+        Shape::Sequence(shapes) | Shape::Repeated(shapes) => array(items(shapes), None),
+        Shape::Map(shapes) => SchemaObject {
+            object: Some(Box::new(ObjectValidation {
+                additional_properties: (!shapes.is_empty()).then(|| Box::new(any_of(shapes))),
+                ..Default::default()
+            })),
+            ..typed(InstanceType::Object)
+        },
+        Shape::Pairs(keys, values) => {
+            let pair = array(
+                Some(SingleOrVec::Vec(vec![any_of(keys), any_of(values)])),
+                Some(2),
+            );
+            array(Some(SingleOrVec::Single(Box::new(pair.into()))), None)
+        }
+    };
+    schema.into()
+}
+
+/// One schema for a single shape, `anyOf` for several, anything for none.
+fn any_of(shapes: &BTreeSet<Shape>) -> Schema {
+    match shapes.len() {
+        0 => Schema::Bool(true),
+        1 => shape_schema(shapes.first().unwrap()),
+        _ => Schema::Object(SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(shapes.iter().map(shape_schema).collect()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }),
+    }
+}
+
+fn into_object(schema: Schema) -> SchemaObject {
+    match schema {
+        Schema::Object(object) => object,
+        Schema::Bool(_) => SchemaObject::default(),
+    }
+}
+
+fn quote(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{element, from_reader, Element};
+    use serde_json::json;
+    use utils::types::{AZSTD_STRING, AZSTD_VECTOR, FLOAT, INT};
+
+    fn item(name: Option<&str>, weights: &[f32]) -> ObjectStream {
+        let mut fields = vec![element(
+            "m_weights",
+            AZSTD_VECTOR,
+            None,
+            weights
+                .iter()
+                .map(|w| element("element", FLOAT, Some(w.to_be_bytes().to_vec()), vec![]))
+                .collect(),
+        )];
+        if let Some(name) = name {
+            fields.push(Element {
+                name: "AZStd::string".into(),
+                ..element("m_name", AZSTD_STRING, Some(name.into()), vec![])
+            });
+        }
+        fields.push(element(
+            "BaseClass1",
+            Uuid::from_u128(2),
+            None,
+            vec![element(
+                "m_id",
+                INT,
+                Some(1i32.to_be_bytes().to_vec()),
+                vec![],
+            )],
+        ));
+        ObjectStream {
+            version: 3,
+            elements: vec![Element {
+                name_crc: None,
+                field: None,
+                name: "Item".into(),
+                version: Some(2),
+                ..element("", Uuid::from_u128(1), None, fields)
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn schemas() {
+        let mut builder = SchemaBuilder::new();
+        builder.add(&item(Some("Iron"), &[0.5]));
+        let mut other = SchemaBuilder::new();
+        other.add(&item(None, &[]));
+        builder.merge(other);
+
+        assert_eq!(builder.classes().collect::<Vec<_>>(), [&Uuid::from_u128(1)]);
+        let schema = serde_json::to_value(builder.schema(&Uuid::from_u128(1)).unwrap()).unwrap();
+        assert_eq!(schema["title"], "Item");
+        assert_eq!(
+            schema["description"],
+            "{00000000-0000-0000-0000-000000000001}, 2 instances, versions 2"
+        );
+        assert_eq!(schema["required"], json!(["m_id", "m_weights"]));
+        assert_eq!(
+            schema["properties"]["m_weights"],
+            json!({"type": "array", "items": {"type": "number"}})
+        );
+        assert_eq!(
+            schema["properties"]["m_name"],
+            json!({"description": "AZStd::string", "type": "string"})
+        );
+        assert_eq!(
+            builder.summary().lines().nth(1),
+            Some("{00000000-0000-0000-0000-000000000001},Item,2,2,m_weights m_name? m_id")
+        );
+
+        // a borrowed stream gives the same classes as the owned one
+        let mut stream = item(Some("Iron"), &[0.5]);
+        stream.elements.iter_mut().for_each(Element::update_flags);
+        let mut buf = vec![];
+        stream.to_writer(&mut buf).unwrap();
+        let mut owned = SchemaBuilder::new();
+        owned.add(&from_reader(&mut buf.as_slice(), None).unwrap());
+        let mut borrowed = SchemaBuilder::new();
+        borrowed.add_ref(&ObjectStreamRef::parse(&buf, None).unwrap());
+        assert_eq!(borrowed.summary(), owned.summary());
+    }
+}
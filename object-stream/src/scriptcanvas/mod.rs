@@ -0,0 +1,624 @@
+//! Nodes and connections of `.scriptcanvas` graphs, rendered as Graphviz DOT, Mermaid or a
+//! JSON adjacency list.
+//!
+//! The graph data holds `m_nodes` and `m_connections`, both sets of `AZ::Entity`. A node
+//! entity has the node component, the one with `Slots`. A connection entity has a `Connection`
+//! component with a `sourceEndpoint` and `targetEndpoint`, each the node entity id and slot id.
+//! Like slices, fields are matched by name crc so this works without the hash dictionaries.
+
+use crate::{
+    braced, field_crc,
+    slice::{field, find, Component, Entity, EntityId, BASE_CLASSES},
+    Element, Node as _, ObjectStream,
+};
+use serde_json::{json, Map, Value};
+use std::fmt::Write;
+use utils::types::AzValue;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct Graph<'a> {
+    /// The element with `m_nodes` and `m_connections`.
+    pub element: &'a Element,
+    pub nodes: Vec<Node<'a>>,
+    pub connections: Vec<Connection<'a>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Node<'a> {
+    pub entity: Entity<'a>,
+    /// The component with the slots, `None` if the entity has none.
+    pub component: Option<Component<'a>>,
+    pub slots: Vec<Slot>,
+    /// Values of the node component other than its slots, by `/` separated path.
+    pub properties: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slot {
+    pub id: Option<Uuid>,
+    pub name: String,
+    pub kind: Option<SlotKind>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotKind {
+    ExecutionIn,
+    ExecutionOut,
+    DataIn,
+    DataOut,
+}
+
+#[derive(Debug, Clone)]
+pub struct Connection<'a> {
+    pub element: &'a Element,
+    pub source: Endpoint,
+    pub target: Endpoint,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Endpoint {
+    pub node: Option<EntityId>,
+    pub slot: Option<Uuid>,
+}
+
+impl ObjectStream {
+    /// The Script Canvas graph in this stream, `None` if it has no graph data.
+    pub fn script_canvas(&self) -> Option<Graph<'_>> {
+        self.elements.iter().find_map(Graph::from_element)
+    }
+}
+
+impl<'a> Graph<'a> {
+    /// Finds the graph data in `root` or its descendants.
+    pub fn from_element(root: &'a Element) -> Option<Self> {
+        let element = find(root, &|e| {
+            field(e, "m_nodes").is_some() && field(e, "m_connections").is_some()
+        })?;
+        let entities = |name| -> Vec<Entity<'a>> {
+            field(element, name)
+                .map(|set| set.elements.iter().map(Entity::from_element).collect())
+                .unwrap_or_default()
+        };
+        let nodes = entities("m_nodes").into_iter().map(Node::new).collect();
+        let connections = entities("m_connections")
+            .iter()
+            .filter_map(Connection::from_entity)
+            .collect();
+        Some(Self {
+            element,
+            nodes,
+            connections,
+        })
+    }
+
+    /// Position of the node with entity id `id`.
+    pub fn position(&self, id: EntityId) -> Option<usize> {
+        self.nodes
+            .iter()
+            .position(|node| node.entity.id == Some(id))
+    }
+
+    /// The slot an endpoint points at.
+    pub fn slot(&self, endpoint: &Endpoint) -> Option<&Slot> {
+        let node = &self.nodes[self.position(endpoint.node?)?];
+        node.slots.iter().find(|slot| slot.id == endpoint.slot)
+    }
+
+    /// Whether the connection carries execution or data, from the kind of its source slot.
+    pub fn is_execution(&self, connection: &Connection<'_>) -> Option<bool> {
+        let kind = self.slot(&connection.source)?.kind?;
+        Some(matches!(
+            kind,
+            SlotKind::ExecutionIn | SlotKind::ExecutionOut
+        ))
+    }
+
+    /// Connections with both nodes in the graph, as node positions.
+    fn edges(&self) -> impl Iterator<Item = (usize, usize, &Connection<'a>)> {
+        self.connections.iter().filter_map(|connection| {
+            let from = self.position(connection.source.node?)?;
+            let to = self.position(connection.target.node?)?;
+            Some((from, to, connection))
+        })
+    }
+
+    fn edge_label(&self, connection: &Connection<'_>) -> String {
+        let name = |endpoint| match self.slot(endpoint) {
+            Some(slot) => slot.name.as_str(),
+            None => "?",
+        };
+        format!(
+            "{} → {}",
+            name(&connection.source),
+            name(&connection.target)
+        )
+    }
+
+    /// A Graphviz `digraph` named `name`. Execution flow is solid, data dashed.
+    pub fn to_dot(&self, name: &str) -> String {
+        let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut dot = format!("digraph \"{}\" {{\n", quote(name));
+        dot.push_str("    rankdir=LR;\n    node [shape=box];\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let label = node.label().iter().map(|l| quote(l)).collect::<Vec<_>>();
+            let _ = writeln!(dot, "    n{i} [label=\"{}\"];", label.join("\\n"));
+        }
+        for (from, to, connection) in self.edges() {
+            let style = match self.is_execution(connection) {
+                Some(false) => ", style=dashed",
+                _ => "",
+            };
+            let label = quote(&self.edge_label(connection));
+            let _ = writeln!(dot, "    n{from} -> n{to} [label=\"{label}\"{style}];");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// A Mermaid `flowchart`. Execution flow is solid, data dotted.
+    pub fn to_mermaid(&self) -> String {
+        let quote = |s: &str| {
+            s.replace('&', "#amp;")
+                .replace('"', "#quot;")
+                .replace('<', "#lt;")
+                .replace('>', "#gt;")
+        };
+        let mut mermaid = String::from("flowchart LR\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let label = node.label().iter().map(|l| quote(l)).collect::<Vec<_>>();
+            let _ = writeln!(mermaid, "    n{i}[\"{}\"]", label.join("<br/>"));
+        }
+        for (from, to, connection) in self.edges() {
+            let arrow = match self.is_execution(connection) {
+                Some(false) => "-.->",
+                _ => "-->",
+            };
+            let label = quote(&self.edge_label(connection));
+            let _ = writeln!(mermaid, "    n{from} {arrow}|\"{label}\"| n{to}");
+        }
+        mermaid
+    }
+
+    /// Nodes with their slots and properties, connections with both endpoints, and the
+    /// entity ids each node connects to.
+    pub fn to_json(&self) -> Value {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|node| {
+                let slots = node
+                    .slots
+                    .iter()
+                    .map(|slot| {
+                        json!({
+                            "id": slot.id.as_ref().map(braced),
+                            "name": slot.name,
+                            "kind": slot.kind.map(SlotKind::as_str),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let properties = node
+                    .properties
+                    .iter()
+                    .map(|(path, value)| (path.clone(), Value::String(value.clone())))
+                    .collect::<Map<_, _>>();
+                json!({
+                    "id": node.entity.id.map(|id| id.0),
+                    "name": node.entity.name,
+                    "type": node.type_name(),
+                    "typeId": node.component.as_ref().map(|c| braced(&c.type_id)),
+                    "slots": slots,
+                    "properties": properties,
+                })
+            })
+            .collect::<Vec<_>>();
+        let endpoint = |endpoint: &Endpoint| {
+            json!({
+                "node": endpoint.node.map(|id| id.0),
+                "slot": endpoint.slot.as_ref().map(braced),
+                "slotName": self.slot(endpoint).map(|slot| slot.name.as_str()),
+            })
+        };
+        let connections = self
+            .connections
+            .iter()
+            .map(|connection| {
+                let kind = self
+                    .is_execution(connection)
+                    .map(|execution| match execution {
+                        true => "execution",
+                        false => "data",
+                    });
+                json!({
+                    "source": endpoint(&connection.source),
+                    "target": endpoint(&connection.target),
+                    "kind": kind,
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut adjacency = Map::new();
+        for node in &self.nodes {
+            let Some(id) = node.entity.id else { continue };
+            let targets = self
+                .connections
+                .iter()
+                .filter(|connection| connection.source.node == Some(id))
+                .filter_map(|connection| connection.target.node.map(|id| id.0))
+                .collect::<Vec<_>>();
+            adjacency.insert(id.0.to_string(), targets.into());
+        }
+        json!({
+            "nodes": nodes,
+            "connections": connections,
+            "adjacency": adjacency,
+        })
+    }
+}
+
+impl<'a> Node<'a> {
+    pub fn new(entity: Entity<'a>) -> Self {
+        let component = entity
+            .components
+            .iter()
+            .find(|component| component.field("Slots").is_some())
+            .cloned();
+        let slots = component
+            .as_ref()
+            .and_then(|component| component.field("Slots"))
+            .map(|slots| slots.elements.iter().map(Slot::from_element).collect())
+            .unwrap_or_default();
+        let mut properties = vec![];
+        if let Some(component) = &component {
+            collect_properties(component.element, "", &mut properties);
+        }
+        Self {
+            entity,
+            component,
+            slots,
+            properties,
+        }
+    }
+
+    /// The node class without its namespaces, else the type id.
+    pub fn type_name(&self) -> String {
+        match &self.component {
+            Some(component) => {
+                // namespaces inside template arguments are kept
+                let name = &component.type_name;
+                let (name, arguments) = name.split_at(name.find('<').unwrap_or(name.len()));
+                match name.rsplit("::").next() {
+                    Some(name) if !name.is_empty() => format!("{name}{arguments}"),
+                    _ => braced(&component.type_id),
+                }
+            }
+            None => "?".to_owned(),
+        }
+    }
+
+    /// The type name, and the entity name when it says something more.
+    fn label(&self) -> Vec<String> {
+        let type_name = self.type_name();
+        let name = &self.entity.name;
+        match name.is_empty() || *name == type_name {
+            true => vec![type_name],
+            false => vec![type_name, name.clone()],
+        }
+    }
+}
+
+impl Slot {
+    pub fn from_element(element: &Element) -> Self {
+        let id = match field(element, "id")
+            .and_then(|id| field(id, "m_id"))
+            .and_then(Element::az_value)
+        {
+            Some(AzValue::Uuid(id)) => Some(id),
+            _ => None,
+        };
+        Self {
+            id,
+            name: field(element, "Name")
+                .and_then(Element::value)
+                .unwrap_or_default(),
+            kind: SlotKind::from_element(element),
+        }
+    }
+}
+
+impl SlotKind {
+    /// From the `Descriptor` connection and slot types, or the `type` of older slots.
+    fn from_element(slot: &Element) -> Option<Self> {
+        if let Some(descriptor) = field(slot, "Descriptor") {
+            let connection = int(field(descriptor, "ConnectionType")?)?;
+            let slot_type = int(field(descriptor, "SlotType")?)?;
+            return match (slot_type, connection) {
+                (1, 1) => Some(SlotKind::ExecutionIn),
+                (1, 2) => Some(SlotKind::ExecutionOut),
+                (2, 1) => Some(SlotKind::DataIn),
+                (2, 2) => Some(SlotKind::DataOut),
+                _ => None,
+            };
+        }
+        match int(field(slot, "type")?)? {
+            1 => Some(SlotKind::ExecutionIn),
+            // latent outputs continue execution later
+            2 | 5 => Some(SlotKind::ExecutionOut),
+            3 => Some(SlotKind::DataIn),
+            4 => Some(SlotKind::DataOut),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SlotKind::ExecutionIn => "execution_in",
+            SlotKind::ExecutionOut => "execution_out",
+            SlotKind::DataIn => "data_in",
+            SlotKind::DataOut => "data_out",
+        }
+    }
+}
+
+impl<'a> Connection<'a> {
+    /// The connection of an entity of `m_connections`.
+    pub fn from_entity(entity: &Entity<'a>) -> Option<Self> {
+        entity.components.iter().find_map(|component| {
+            let source = component.field("sourceEndpoint")?;
+            let target = component.field("targetEndpoint")?;
+            Some(Self {
+                element: component.element,
+                source: Endpoint::from_element(source),
+                target: Endpoint::from_element(target),
+            })
+        })
+    }
+}
+
+impl Endpoint {
+    pub fn from_element(element: &Element) -> Self {
+        Self {
+            node: field(element, "nodeId").and_then(EntityId::from_element),
+            slot: match field(element, "slotId")
+                .and_then(|id| field(id, "m_id"))
+                .and_then(Element::az_value)
+            {
+                Some(AzValue::Uuid(id)) => Some(id),
+                _ => None,
+            },
+        }
+    }
+}
+
+fn int(element: &Element) -> Option<i64> {
+    match element.az_value()? {
+        AzValue::I8(v) => Some(v.into()),
+        AzValue::I16(v) => Some(v.into()),
+        AzValue::I32(v) => Some(v.into()),
+        AzValue::I64(v) => Some(v),
+        AzValue::U8(v) => Some(v.into()),
+        AzValue::U16(v) => Some(v.into()),
+        AzValue::U32(v) => Some(v.into()),
+        AzValue::U64(v) => i64::try_from(v).ok(),
+        _ => None,
+    }
+}
+
+/// Values under `element`, leaving out the slots and the component id. Base class fields keep
+/// the path of the class, container items are numbered.
+fn collect_properties(element: &Element, prefix: &str, properties: &mut Vec<(String, String)>) {
+    let skipped = [field_crc("Slots"), field_crc("Id")];
+    for (i, child) in element.elements.iter().enumerate() {
+        let crc = child.name_crc.unwrap_or_default();
+        if BASE_CLASSES.contains(&crc) {
+            collect_properties(child, prefix, properties);
+            continue;
+        }
+        if prefix.is_empty() && skipped.contains(&crc) {
+            continue;
+        }
+        let segment = match crc == field_crc("element") {
+            true => i.to_string(),
+            false => child.path_segment(),
+        };
+        let path = match prefix.is_empty() {
+            true => segment,
+            false => format!("{prefix}/{segment}"),
+        };
+        if let Some(value) = child.value() {
+            properties.push((path.clone(), value));
+        }
+        collect_properties(child, &path, properties);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        element,
+        slice::{ENTITY, ENTITY_ID},
+    };
+    use utils::types::{AZSTD_STRING, AZSTD_VECTOR, AZ_U64, AZ_UUID, INT};
+
+    fn entity_id(field: &str, id: u64) -> Element {
+        element(
+            field,
+            ENTITY_ID,
+            None,
+            vec![element(
+                "id",
+                AZ_U64,
+                Some(id.to_be_bytes().to_vec()),
+                vec![],
+            )],
+        )
+    }
+
+    fn string(field: &str, value: &str) -> Element {
+        element(field, AZSTD_STRING, Some(value.as_bytes().to_vec()), vec![])
+    }
+
+    fn slot_id(field: &str, id: u128) -> Element {
+        element(
+            field,
+            Uuid::from_u128(0x10),
+            None,
+            vec![element(
+                "m_id",
+                AZ_UUID,
+                Some(Uuid::from_u128(id).as_bytes().to_vec()),
+                vec![],
+            )],
+        )
+    }
+
+    fn slot(id: u128, name: &str, slot_type: i32, connection: i32) -> Element {
+        let int =
+            |field, value: i32| element(field, INT, Some(value.to_be_bytes().to_vec()), vec![]);
+        element(
+            "element",
+            Uuid::from_u128(0x11),
+            None,
+            vec![
+                slot_id("id", id),
+                string("Name", name),
+                element(
+                    "Descriptor",
+                    Uuid::from_u128(0x12),
+                    None,
+                    vec![
+                        int("ConnectionType", connection),
+                        int("SlotType", slot_type),
+                    ],
+                ),
+            ],
+        )
+    }
+
+    fn entity(id: u64, name: &str, component: Element) -> Element {
+        element(
+            "element",
+            ENTITY,
+            None,
+            vec![
+                entity_id("Id", id),
+                string("Name", name),
+                element("Components", AZSTD_VECTOR, None, vec![component]),
+            ],
+        )
+    }
+
+    fn node(id: u64, type_name: &str, mut fields: Vec<Element>, slots: Vec<Element>) -> Element {
+        fields.push(element("Slots", AZSTD_VECTOR, None, slots));
+        let mut component = element("element", Uuid::from_u128(id.into()), None, fields);
+        component.name = type_name.to_owned();
+        let name = type_name.split('<').next().unwrap();
+        entity(id, name.rsplit("::").next().unwrap(), component)
+    }
+
+    fn connection(id: u64, source: (u64, u128), target: (u64, u128)) -> Element {
+        let endpoint = |field, (node, slot)| {
+            element(
+                field,
+                Uuid::from_u128(0x13),
+                None,
+                vec![entity_id("nodeId", node), slot_id("slotId", slot)],
+            )
+        };
+        let component = element(
+            "element",
+            Uuid::from_u128(0x14),
+            None,
+            vec![
+                endpoint("sourceEndpoint", source),
+                endpoint("targetEndpoint", target),
+            ],
+        );
+        entity(id, "Connection", component)
+    }
+
+    #[test]
+    fn graph() {
+        let nodes = vec![
+            node(
+                1,
+                "ScriptCanvas::Nodes::Core::Start",
+                vec![],
+                vec![slot(0xA1, "Out", 1, 2)],
+            ),
+            node(
+                2,
+                "ScriptCanvas::Nodes::Math::Number",
+                vec![],
+                vec![slot(0xB1, "Number", 2, 2)],
+            ),
+            node(
+                3,
+                "ScriptCanvas::Nodes::Debug::Log",
+                vec![string("m_format", "value \"{0}\"")],
+                vec![slot(0xC1, "In", 1, 1), slot(0xC2, "Value", 2, 1)],
+            ),
+            node(
+                4,
+                "ScriptCanvas::Nodes::Core::ForEach<AZStd::vector<float>>",
+                vec![],
+                vec![],
+            ),
+        ];
+        let connections = vec![
+            connection(10, (1, 0xA1), (3, 0xC1)),
+            connection(11, (2, 0xB1), (3, 0xC2)),
+        ];
+        let root = element(
+            "m_graphData",
+            Uuid::from_u128(0x20),
+            None,
+            vec![
+                element("m_nodes", AZSTD_VECTOR, None, nodes),
+                element("m_connections", AZSTD_VECTOR, None, connections),
+            ],
+        );
+        let stream = ObjectStream {
+            version: 3,
+            elements: vec![element("root", Uuid::from_u128(0x21), None, vec![root])],
+            ..Default::default()
+        };
+
+        let graph = stream.script_canvas().unwrap();
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(graph.nodes[0].type_name(), "Start");
+        assert_eq!(graph.nodes[3].type_name(), "ForEach<AZStd::vector<float>>");
+        assert_eq!(graph.nodes[2].slots[1].kind, Some(SlotKind::DataIn));
+        assert_eq!(
+            graph.nodes[2].properties,
+            [("m_format".to_owned(), "value \"{0}\"".to_owned())]
+        );
+        assert_eq!(graph.connections.len(), 2);
+        assert_eq!(graph.is_execution(&graph.connections[0]), Some(true));
+        assert_eq!(graph.is_execution(&graph.connections[1]), Some(false));
+
+        let dot = graph.to_dot("test");
+        assert!(
+            dot.contains("    n0 -> n2 [label=\"Out → In\"];\n"),
+            "{dot}"
+        );
+        assert!(dot.contains("    n1 -> n2 [label=\"Number → Value\", style=dashed];\n"));
+
+        let mermaid = graph.to_mermaid();
+        assert!(mermaid.starts_with("flowchart LR\n    n0[\"Start\"]\n"));
+        assert!(mermaid.contains("    n1 -.->|\"Number → Value\"| n2\n"));
+        assert!(
+            mermaid.contains("    n3[\"ForEach#lt;AZStd::vector#lt;float#gt;#gt;<br/>ForEach\"]\n")
+        );
+
+        let json = graph.to_json();
+        assert_eq!(
+            json["adjacency"],
+            json!({"1": [3], "2": [3], "3": [], "4": []})
+        );
+        assert_eq!(json["connections"][1]["kind"], "data");
+        assert_eq!(json["connections"][0]["target"]["slotName"], "In");
+        assert_eq!(json["nodes"][2]["slots"][0]["kind"], "execution_in");
+    }
+}
@@ -1,448 +1,497 @@
 use crate::{
     error::{Error, Result},
-    ST_BINARYFLAG_HAS_VALUE, ST_BINARY_VALUE_SIZE_MASK,
+    field_crc, Element, ObjectStream, OBJECT_STREAM_VERSION,
 };
-use serde::{ser, Serialize};
+use serde::{ser, ser::Error as _, Serialize};
 use std::io::Write;
+use utils::types::*;
+use uuid::Uuid;
+
+/// Serializes values into [`Element`]s.
+///
+/// Type UUIDs come from the serde name of structs, newtype structs and enums, written as
+/// `#[serde(rename = "{UUID}")]` or `#[serde(rename = "{UUID}:version")]`. Primitives use the
+/// engine's built in types, sequences are written as `AZStd::vector` and maps as `AZStd::map`
+/// containers of `element` children, and `None` fields are left out of the stream. Values
+/// without a type UUID are an error.
+pub struct Serializer;
+
+fn type_info(name: &str) -> Option<(Uuid, Option<u8>)> {
+    let (uuid, version) = match name.split_once(':') {
+        Some((uuid, version)) => (uuid, Some(version.parse().ok()?)),
+        None => (name, None),
+    };
+    Some((Uuid::parse_str(uuid).ok()?, version))
+}
 
-pub struct Serializer<W> {
-    writer: W,
-    version: Option<u32>,
-    flags: Option<u8>,
-    capture: bool,
-    write_size: bool,
-    size: Option<u8>,
+fn value(id: Uuid, data: Vec<u8>) -> Option<Element> {
+    Some(Element {
+        id,
+        data: Some(data),
+        ..Default::default()
+    })
 }
 
-impl<W: Write> Serializer<W> {
-    pub fn new(writer: W) -> Self {
-        Serializer {
-            writer,
-            version: None,
-            flags: None,
-            capture: false,
-            write_size: false,
-            size: None,
-        }
+fn container(name: &str) -> Result<Element> {
+    let (id, version) = type_info(name).ok_or_else(|| {
+        Error::Message(format!(
+            "{name}: no type UUID, rename the type to \"{{UUID}}\" or \"{{UUID}}:version\""
+        ))
+    })?;
+    Ok(Element {
+        id,
+        version,
+        ..Default::default()
+    })
+}
+
+fn push_field(parent: &mut Element, field: &str, element: Option<Element>) -> Result<()> {
+    if let Some(mut element) = element {
+        typed(&element, field)?;
+        element.name_crc = Some(field_crc(field));
+        element.field = Some(field.to_owned());
+        parent.elements.push(element);
+    }
+    Ok(())
+}
+
+/// Only raw bytes come out without a type, until a newtype struct names it.
+fn typed(element: &Element, field: &str) -> Result<()> {
+    match element.id.is_nil() {
+        true => Err(Error::Message(format!(
+            "{field}: bytes need a newtype struct named after their type UUID"
+        ))),
+        false => Ok(()),
     }
 }
 
-impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
-    type Ok = ();
+impl ser::Serializer for Serializer {
+    type Ok = Option<Element>;
     type Error = Error;
 
-    type SerializeSeq = Self;
-    type SerializeTuple = Self;
-    type SerializeTupleStruct = Self;
-    type SerializeTupleVariant = Self;
-    type SerializeMap = Self;
-    type SerializeStruct = Self;
-    type SerializeStructVariant = Self;
+    type SerializeSeq = SerializeContainer;
+    type SerializeTuple = SerializeContainer;
+    type SerializeTupleStruct = SerializeContainer;
+    type SerializeTupleVariant = ser::Impossible<Self::Ok, Error>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeContainer;
+    type SerializeStructVariant = ser::Impossible<Self::Ok, Error>;
 
-    fn serialize_bool(self, _v: bool) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn is_human_readable(&self) -> bool {
+        false
     }
 
-    fn serialize_i8(self, _v: i8) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        Ok(value(BOOL, vec![v as u8]))
     }
 
-    fn serialize_i16(self, _v: i16) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        Ok(value(AZ_S8, v.to_be_bytes().to_vec()))
     }
 
-    fn serialize_i32(self, _v: i32) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        Ok(value(SHORT, v.to_be_bytes().to_vec()))
     }
 
-    fn serialize_i64(self, _v: i64) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        Ok(value(INT, v.to_be_bytes().to_vec()))
     }
 
-    fn serialize_u8(self, v: u8) -> std::result::Result<Self::Ok, Self::Error> {
-        if self.capture {
-            self.flags = Some(v);
-            self.capture = false;
-        }
-        self.writer.write_all(&[v]).map_err(Error::Io)
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        Ok(value(AZ_S64, v.to_be_bytes().to_vec()))
     }
 
-    fn serialize_u16(self, _v: u16) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        Ok(value(UNSIGNED_CHAR, vec![v]))
     }
 
-    fn serialize_u32(self, v: u32) -> std::result::Result<Self::Ok, Self::Error> {
-        if self.version.is_none() {
-            self.version = Some(v);
-        }
-        self.writer.write_all(&v.to_be_bytes()).map_err(Error::Io)
-    }
-
-    fn serialize_u64(self, v: u64) -> std::result::Result<Self::Ok, Self::Error> {
-        if self.write_size {
-            self.write_size = false;
-            return match self.size {
-                Some(1) => self.serialize_u8(v as u8),
-                Some(2) => self.serialize_u16(v as u16),
-                Some(4) => self.serialize_u32(v as u32),
-                _ => Err(Error::ExpectedValue),
-            };
-        }
-        self.writer.write_all(&v.to_be_bytes()).map_err(Error::Io)
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        Ok(value(UNSIGNED_SHORT, v.to_be_bytes().to_vec()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        Ok(value(UNSIGNED_INT, v.to_be_bytes().to_vec()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        Ok(value(AZ_U64, v.to_be_bytes().to_vec()))
     }
 
-    fn serialize_f32(self, _v: f32) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        Ok(value(FLOAT, v.to_be_bytes().to_vec()))
     }
 
-    fn serialize_f64(self, _v: f64) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        Ok(value(DOUBLE, v.to_be_bytes().to_vec()))
     }
 
-    fn serialize_char(self, v: char) -> std::result::Result<Self::Ok, Self::Error> {
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
         let mut buf = [0; 4];
-        let c = v.encode_utf8(&mut buf);
-        self.writer.write_all(c.as_bytes()).map_err(Error::Io)
+        self.serialize_str(v.encode_utf8(&mut buf))
     }
 
-    fn serialize_str(self, v: &str) -> std::result::Result<Self::Ok, Self::Error> {
-        self.writer.write_all(v.as_bytes()).map_err(Error::Io)
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        Ok(value(AZSTD_STRING, v.as_bytes().to_vec()))
     }
 
-    fn serialize_bytes(self, v: &[u8]) -> std::result::Result<Self::Ok, Self::Error> {
-        self.writer.write_all(v).map_err(Error::Io)
+    /// Raw bytes have no type of their own, wrap them in a newtype struct named after the
+    /// type UUID.
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        Ok(value(Uuid::nil(), v.to_vec()))
     }
 
-    fn serialize_none(self) -> std::result::Result<Self::Ok, Self::Error> {
-        Ok(())
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
     }
 
-    fn serialize_some<T>(self, value: &T) -> std::result::Result<Self::Ok, Self::Error>
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut *self)
+        value.serialize(self)
     }
 
-    fn serialize_unit(self) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Err(Error::Message("() has no type UUID".into()))
     }
 
-    fn serialize_unit_struct(
-        self,
-        _name: &'static str,
-    ) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok> {
+        Ok(Some(container(name)?))
     }
 
+    /// Enums are stored as their variant index with the enum's type, or as an `unsigned int`
+    /// when the enum isn't named after one.
     fn serialize_unit_variant(
         self,
-        _name: &'static str,
-        _variant_index: u32,
+        name: &'static str,
+        variant_index: u32,
         _variant: &'static str,
-    ) -> std::result::Result<Self::Ok, Self::Error> {
-        // let f = format!("name: {} idx: {} variant: {}", name, variant_index, variant);
-        // dbg!("IM HERE SERIALIZING A UNIT VARIANT", f);
-        // Ok(())
-        todo!()
+    ) -> Result<Self::Ok> {
+        let (id, version) = type_info(name).unwrap_or((UNSIGNED_INT, None));
+        Ok(Some(Element {
+            id,
+            version,
+            data: Some(variant_index.to_be_bytes().to_vec()),
+            ..Default::default()
+        }))
     }
 
-    fn serialize_newtype_struct<T>(
-        self,
-        _name: &'static str,
-        value: &T,
-    ) -> std::result::Result<Self::Ok, Self::Error>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut *self)
+        let mut element = value.serialize(self)?;
+        if let (Some(element), Some((id, version))) = (element.as_mut(), type_info(name)) {
+            element.id = id;
+            element.version = version;
+        }
+        Ok(element)
     }
 
     fn serialize_newtype_variant<T>(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _value: &T,
-    ) -> std::result::Result<Self::Ok, Self::Error>
+    ) -> Result<Self::Ok>
     where
         T: ?Sized + Serialize,
     {
-        todo!()
+        Err(Error::custom(format!(
+            "{name}::{variant}: enum variants with data can't be stored in an ObjectStream"
+        )))
     }
 
-    fn serialize_seq(
-        self,
-        _len: Option<usize>,
-    ) -> std::result::Result<Self::SerializeSeq, Self::Error> {
-        Ok(self)
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SerializeContainer(Element {
+            id: AZSTD_VECTOR,
+            ..Default::default()
+        }))
     }
 
-    fn serialize_tuple(
-        self,
-        _len: usize,
-    ) -> std::result::Result<Self::SerializeTuple, Self::Error> {
-        Ok(self)
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
     }
 
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         _len: usize,
-    ) -> std::result::Result<Self::SerializeTupleStruct, Self::Error> {
-        Ok(self)
+    ) -> Result<Self::SerializeTupleStruct> {
+        Ok(SerializeContainer(container(name)?))
     }
 
     fn serialize_tuple_variant(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
-    ) -> std::result::Result<Self::SerializeTupleVariant, Self::Error> {
-        Ok(self)
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::custom(format!(
+            "{name}::{variant}: enum variants with data can't be stored in an ObjectStream"
+        )))
     }
 
-    fn serialize_map(
-        self,
-        _len: Option<usize>,
-    ) -> std::result::Result<Self::SerializeMap, Self::Error> {
-        Ok(self)
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(SerializeMap {
+            element: Element {
+                id: AZSTD_MAP,
+                ..Default::default()
+            },
+            key: None,
+        })
     }
 
-    fn serialize_struct(
-        self,
-        _name: &'static str,
-        _len: usize,
-    ) -> std::result::Result<Self::SerializeStruct, Self::Error> {
-        self.flags = None;
-        Ok(self)
+    fn serialize_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(SerializeContainer(container(name)?))
     }
 
     fn serialize_struct_variant(
         self,
-        _name: &'static str,
+        name: &'static str,
         _variant_index: u32,
-        _variant: &'static str,
+        variant: &'static str,
         _len: usize,
-    ) -> std::result::Result<Self::SerializeStructVariant, Self::Error> {
-        Ok(self)
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::custom(format!(
+            "{name}::{variant}: enum variants with data can't be stored in an ObjectStream"
+        )))
     }
 }
 
-impl<'a, W: Write> ser::SerializeStruct for &'a mut Serializer<W> {
-    type Ok = ();
+pub struct SerializeContainer(Element);
+
+impl ser::SerializeStruct for SerializeContainer {
+    type Ok = Option<Element>;
     type Error = Error;
 
-    fn serialize_field<T>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> std::result::Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        match key {
-            "flags" => {
-                self.capture = true;
-            }
-            "data_size" => {
-                self.write_size = true;
-                if let Some(flags) = self.flags {
-                    if flags & ST_BINARYFLAG_HAS_VALUE > 0 {
-                        let size = flags & ST_BINARY_VALUE_SIZE_MASK;
-                        match size {
-                            1 => self.size = Some(1),
-                            2 => self.size = Some(2),
-                            4 => self.size = Some(4),
-                            _ => return Ok(()),
-                        };
-                        return value.serialize(&mut **self);
-                    }
-                }
-
-                return Ok(());
-            }
-            "elements" => {
-                value.serialize(&mut **self)?;
-                self.writer.write_all(&mut [0]).map_err(Error::Io)?;
-                return Ok(());
-            }
-            _ => {}
-        }
-        value.serialize(&mut **self)
+        push_field(&mut self.0, key, value.serialize(Serializer)?)
     }
 
-    fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        Ok(())
-    }
-
-    fn skip_field(&mut self, key: &'static str) -> std::result::Result<(), Self::Error> {
-        let _ = key;
-        Ok(())
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0))
     }
 }
 
-impl<'a, W: Write> ser::SerializeMap for &'a mut Serializer<W> {
-    type Ok = ();
+impl ser::SerializeSeq for SerializeContainer {
+    type Ok = Option<Element>;
     type Error = Error;
 
-    fn serialize_key<T>(&mut self, _key: &T) -> std::result::Result<(), Self::Error>
-    where
-        T: ?Sized + Serialize,
-    {
-        todo!()
-    }
-
-    fn serialize_value<T>(&mut self, _value: &T) -> std::result::Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        todo!()
+        push_field(&mut self.0, "element", value.serialize(Serializer)?)
     }
 
-    fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0))
     }
 }
 
-impl<'a, W: Write> ser::SerializeSeq for &'a mut Serializer<W> {
-    type Ok = ();
+impl ser::SerializeTuple for SerializeContainer {
+    type Ok = Option<Element>;
     type Error = Error;
 
-    fn serialize_element<T>(&mut self, value: &T) -> std::result::Result<(), Self::Error>
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        ser::SerializeSeq::serialize_element(self, value)
     }
 
-    fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        Ok(())
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0))
     }
 }
-impl<'a, W: Write> ser::SerializeStructVariant for &'a mut Serializer<W> {
-    type Ok = ();
+
+impl ser::SerializeTupleStruct for SerializeContainer {
+    type Ok = Option<Element>;
     type Error = Error;
 
-    fn serialize_field<T>(
-        &mut self,
-        _key: &'static str,
-        _value: &T,
-    ) -> std::result::Result<(), Self::Error>
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        todo!()
+        ser::SerializeSeq::serialize_element(self, value)
     }
 
-    fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0))
     }
 }
-impl<'a, W: Write> ser::SerializeTuple for &'a mut Serializer<W> {
-    type Ok = ();
-    type Error = Error;
 
-    fn serialize_element<T>(&mut self, value: &T) -> std::result::Result<(), Self::Error>
-    where
-        T: ?Sized + Serialize,
-    {
-        value.serialize(&mut **self)
-    }
-
-    fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        Ok(())
-    }
+/// Maps are containers of `AZStd::pair` elements holding `value1` and `value2`.
+pub struct SerializeMap {
+    element: Element,
+    key: Option<Option<Element>>,
 }
-impl<'a, W: Write> ser::SerializeTupleStruct for &'a mut Serializer<W> {
-    type Ok = ();
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Option<Element>;
     type Error = Error;
 
-    fn serialize_field<T>(&mut self, _value: &T) -> std::result::Result<(), Self::Error>
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        todo!()
-    }
-
-    fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
+        self.key = Some(key.serialize(Serializer)?);
+        Ok(())
     }
-}
-impl<'a, W: Write> ser::SerializeTupleVariant for &'a mut Serializer<W> {
-    type Ok = ();
-    type Error = Error;
 
-    fn serialize_field<T>(&mut self, _value: &T) -> std::result::Result<(), Self::Error>
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        todo!()
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::custom("map value serialized before its key"))?;
+        let mut pair = Element {
+            id: AZSTD_PAIR,
+            ..Default::default()
+        };
+        push_field(&mut pair, "value1", key)?;
+        push_field(&mut pair, "value2", value.serialize(Serializer)?)?;
+        push_field(&mut self.element, "element", Some(pair))
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.element))
     }
+}
 
-    fn end(self) -> std::result::Result<Self::Ok, Self::Error> {
-        todo!()
-    }
+/// Serializes `value` into an [`Element`] with its flags and sizes filled in.
+pub fn to_element<T>(value: &T) -> Result<Element>
+where
+    T: ?Sized + Serialize,
+{
+    let mut element = value
+        .serialize(Serializer)?
+        .ok_or_else(|| Error::custom("nothing to serialize"))?;
+    typed(&element, "root")?;
+    element.update_flags();
+    Ok(element)
 }
 
+/// Writes `value` as the root element of a binary ObjectStream.
 pub fn to_writer<T, W>(value: &T, writer: &mut W) -> Result<()>
 where
-    T: Serialize,
+    T: ?Sized + Serialize,
     W: Write,
 {
-    let mut serializer = Serializer::new(writer);
-    value.serialize(&mut serializer)?;
-    Ok(())
+    let stream = ObjectStream {
+        version: OBJECT_STREAM_VERSION,
+        elements: vec![to_element(value)?],
+        ..Default::default()
+    };
+    stream.to_writer(writer).map_err(Error::Io)
 }
 
 #[cfg(test)]
 mod tests {
-    // use std::io::Cursor;
-
-    // use super::*;
-    // use crate::{from_reader, ObjectStream};
-    // use serde::Serialize;
+    use super::*;
+    use crate::{from_slice, ST_BINARYFLAG_EXTRA_SIZE_FIELD};
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename = "{4F7D1E9B-2C0A-4B6E-9D3F-8A1B2C3D4E5F}:2")]
+    struct Item {
+        m_id: u32,
+        m_name: String,
+        m_weight: f32,
+        m_enabled: bool,
+        m_tags: Vec<i16>,
+        m_stats: BTreeMap<String, u64>,
+        m_parent: Option<u64>,
+        m_description: String,
+    }
 
     #[test]
     fn test_struct() {
-        // let byt =
-        //     include_bytes!("E:/Extract/nw live/sharedassets/genericassets/fuelcategory.fueldb");
-
-        // let test: ObjectStream = from_reader(&mut Cursor::new(byt)).unwrap();
-        // let mut buf = vec![];
-        // to_writer(&test, &mut buf).unwrap();
-        // dbg!(&test);
-        // let mut buf2 = vec![];
-        // test.to_writer(&mut buf2).unwrap();
-        // assert_eq!(&buf, byt);
-        // assert_eq!(&buf2, byt);
-    }
-
-    // #[test]
-    // fn test_enum() {
-    //     #[derive(Serialize)]
-    //     enum E {
-    //         Unit,
-    //         Newtype(u32),
-    //         Tuple(u32, u32),
-    //         Struct { a: u32 },
-    //     }
-
-    //     let u = E::Unit;
-    //     let expected = r#""Unit""#;
-    //     assert_eq!(to_string(&u).unwrap(), expected);
-
-    //     let n = E::Newtype(1);
-    //     let expected = r#"{"Newtype":1}"#;
-    //     assert_eq!(to_string(&n).unwrap(), expected);
-
-    //     let t = E::Tuple(1, 2);
-    //     let expected = r#"{"Tuple":[1,2]}"#;
-    //     assert_eq!(to_string(&t).unwrap(), expected);
-
-    //     let s = E::Struct { a: 1 };
-    //     let expected = r#"{"Struct":{"a":1}}"#;
-    //     assert_eq!(to_string(&s).unwrap(), expected);
-    // }
+        let item = Item {
+            m_id: 7,
+            m_name: "Iron".into(),
+            m_weight: 0.25,
+            m_enabled: true,
+            m_tags: vec![1, -2],
+            m_stats: BTreeMap::from([("strength".into(), 5)]),
+            m_parent: None,
+            m_description: "x".repeat(300),
+        };
+
+        let mut buf = vec![];
+        to_writer(&item, &mut buf).unwrap();
+        assert_eq!(&buf[..5], &[0, 0, 0, 0, 3]);
+        assert_eq!(from_slice::<Item>(&buf, None).unwrap(), item);
+
+        let root = to_element(&item).unwrap();
+        assert_eq!(root.id.to_string(), "4f7d1e9b-2c0a-4b6e-9d3f-8a1b2c3d4e5f");
+        assert_eq!(root.version, Some(2));
+        assert!(!root
+            .elements
+            .iter()
+            .any(|e| e.field.as_deref() == Some("m_parent")));
+
+        // header | name | value with a 4 byte inline size
+        let id = &root.elements[0];
+        assert_eq!(id.flags, 0x08 | 0x40 | 0x10 | 4);
+        assert_eq!(id.name_crc, Some(crc32fast::hash(b"m_id")));
+
+        let tags = &root.elements[4];
+        assert_eq!(tags.id, AZSTD_VECTOR);
+        let stats = &root.elements[5];
+        assert_eq!(stats.id, AZSTD_MAP);
+        assert_eq!(stats.elements[0].id, AZSTD_PAIR);
+
+        let description = root.elements.last().unwrap();
+        assert_eq!(
+            description.flags,
+            0x08 | 0x40 | 0x10 | ST_BINARYFLAG_EXTRA_SIZE_FIELD | 2
+        );
+        assert_eq!(description.data_size, Some(300));
+    }
+
+    #[test]
+    fn newtype_type_override() {
+        #[derive(Serialize)]
+        #[serde(rename = "{E152C105-A133-4D03-BBF8-3D4B2FBA3E2A}")]
+        struct AzUuid(#[serde(with = "serde_bytes")] [u8; 16]);
+
+        let element = to_element(&AzUuid([1; 16])).unwrap();
+        assert_eq!(element.id, AZ_UUID);
+        assert_eq!(element.data, Some(vec![1; 16]));
+        assert_eq!(
+            element.flags,
+            0x08 | 0x10 | ST_BINARYFLAG_EXTRA_SIZE_FIELD | 1
+        );
+    }
+
+    #[test]
+    fn untyped_values() {
+        #[derive(Serialize)]
+        struct Untyped {
+            m_id: u32,
+        }
+
+        #[derive(Serialize)]
+        #[serde(rename = "{4F7D1E9B-2C0A-4B6E-9D3F-8A1B2C3D4E5F}")]
+        struct Blob {
+            #[serde(with = "serde_bytes")]
+            m_data: Vec<u8>,
+        }
+
+        let error = to_element(&Untyped { m_id: 1 }).unwrap_err();
+        assert!(error.to_string().starts_with("Untyped: no type UUID"));
+        let error = to_element(&Blob { m_data: vec![1] }).unwrap_err();
+        assert!(error.to_string().starts_with("m_data: bytes need"));
+        assert!(to_element(&()).is_err());
+    }
 }
@@ -0,0 +1,842 @@
+//! Entities of `.slice` and `.dynamicslice` streams.
+//!
+//! A slice is an `AZ::Entity` with a `SliceComponent`, whose `Entities` are the entities of
+//! the slice. Each entity has an `Id`, a `Name` and its `Components`. Fields are matched by
+//! name crc and types by type id or name, so this works without the hash dictionaries too.
+//!
+//! Slices also instance other slices: `Prefabs` lists the referenced slice assets, each with
+//! its instances. An instance is a copy of the referenced slice's entities with new entity ids
+//! (`EntityIdMap`) and a `DataPatch` of the changes. [`SliceResolver`] loads the references
+//! and applies both, giving the entities that actually spawn.
+
+use crate::{container::Container, field_crc, from_reader, Element, ObjectStream};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    sync::LazyLock,
+};
+use utils::{
+    lumberyard::LumberyardSource,
+    types::{AssetRef, AzValue},
+};
+use uuid::Uuid;
+
+pub const ENTITY: Uuid = Uuid::from_u128(0x75651658_8663_478D_9090_2432DFCAFA44);
+pub const ENTITY_ID: Uuid = Uuid::from_u128(0x6383F1D3_BB27_4E6B_A49A_6409B2059EAA);
+pub const SLICE_COMPONENT: Uuid = Uuid::from_u128(0xAFD304E4_1835_478D_B4B5_BDE9D1D1A0C8);
+pub const TRANSFORM_COMPONENT: Uuid = Uuid::from_u128(0x22B10178_39B6_4C12_BB37_77DB45FDD3B6);
+
+/// Nested instances deeper than this are left out, in case slices instance each other.
+const MAX_DEPTH: usize = 32;
+
+/// Base class fields, searched like fields of the class itself.
+pub(crate) static BASE_CLASSES: LazyLock<Vec<u32>> = LazyLock::new(|| {
+    (1..=4)
+        .map(|i| field_crc(&format!("BaseClass{i}")))
+        .collect()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntityId(pub u64);
+
+impl EntityId {
+    /// Id of entities that were never assigned one.
+    pub const INVALID: EntityId = EntityId(u64::MAX);
+
+    pub(crate) fn from_element(element: &Element) -> Option<Self> {
+        match field(element, "id")?.az_value()? {
+            AzValue::U64(id) => Some(EntityId(id)).filter(|id| *id != Self::INVALID),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Slice<'a> {
+    /// The `SliceComponent`.
+    pub element: &'a Element,
+    pub is_dynamic: bool,
+    pub entities: Vec<Entity<'a>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Entity<'a> {
+    pub element: &'a Element,
+    pub id: Option<EntityId>,
+    pub name: String,
+    pub components: Vec<Component<'a>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Component<'a> {
+    pub element: &'a Element,
+    /// Component id, unique within the entity.
+    pub id: Option<u64>,
+    pub type_id: Uuid,
+    /// Empty when it couldn't be resolved.
+    pub type_name: &'a str,
+}
+
+/// Transforms are the 3x4 matrix, row by row.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransformComponent {
+    pub parent: Option<EntityId>,
+    pub world: Option<[f32; 12]>,
+    pub local: Option<[f32; 12]>,
+    pub is_static: Option<bool>,
+}
+
+/// A referenced slice asset and its instances in this slice.
+#[derive(Debug, Clone)]
+pub struct SliceReference<'a> {
+    pub element: &'a Element,
+    pub asset: Option<AssetRef>,
+    pub instances: Vec<SliceInstance<'a>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SliceInstance<'a> {
+    pub element: &'a Element,
+    /// Entity ids of the referenced slice to the ids of this instance.
+    pub entity_id_map: HashMap<EntityId, EntityId>,
+    pub patch: Option<DataPatch>,
+}
+
+/// Changes to a copy of the referenced slice's entities. Each patch is the address of an
+/// element, as field name crcs, persistent ids (entity and component ids) or container
+/// indices from the root, and the new element as a binary stream, empty to remove it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DataPatch {
+    pub patches: Vec<(Vec<u64>, Vec<u8>)>,
+    /// Patches with an address in a format this can't read.
+    pub unsupported: usize,
+}
+
+/// An entity of a flattened slice.
+#[derive(Debug, Clone)]
+pub struct FlatEntity {
+    /// The `AZ::Entity`, with instance ids and patches applied.
+    pub element: Element,
+    /// Hints of the slice assets instanced to get to this entity, outermost first.
+    pub source: Vec<String>,
+}
+
+impl FlatEntity {
+    pub fn entity(&self) -> Entity<'_> {
+        Entity::from_element(&self.element)
+    }
+}
+
+impl TransformComponent {
+    /// Translation of the local transform.
+    pub fn local_translation(&self) -> Option<[f32; 3]> {
+        self.local.map(|m| [m[3], m[7], m[11]])
+    }
+
+    /// Translation of the world transform.
+    pub fn world_translation(&self) -> Option<[f32; 3]> {
+        self.world.map(|m| [m[3], m[7], m[11]])
+    }
+}
+
+impl ObjectStream {
+    /// The slice in this stream, `None` if it has no `SliceComponent`.
+    pub fn slice(&self) -> Option<Slice<'_>> {
+        self.elements.iter().find_map(Slice::from_element)
+    }
+}
+
+impl<'a> Slice<'a> {
+    /// Finds the `SliceComponent` in `root` or its descendants.
+    pub fn from_element(root: &'a Element) -> Option<Self> {
+        let element = find(root, &|e| is_type(e, SLICE_COMPONENT, "SliceComponent"))?;
+        let entities = field(element, "Entities")
+            .map(|entities| entities.elements.iter().map(Entity::from_element).collect())
+            .unwrap_or_default();
+        let is_dynamic = matches!(
+            field(element, "IsDynamic").and_then(Element::az_value),
+            Some(AzValue::Bool(true))
+        );
+        Some(Self {
+            element,
+            is_dynamic,
+            entities,
+        })
+    }
+
+    pub fn entity(&self, id: EntityId) -> Option<&Entity<'a>> {
+        self.entities.iter().find(|entity| entity.id == Some(id))
+    }
+
+    /// The slices referenced in `Prefabs`, with their instances.
+    pub fn references(&self) -> Vec<SliceReference<'a>> {
+        let Some(prefabs) = field(self.element, "Prefabs") else {
+            return vec![];
+        };
+        prefabs
+            .elements
+            .iter()
+            .map(|reference| SliceReference {
+                element: reference,
+                asset: match field(reference, "Asset").and_then(Element::az_value) {
+                    Some(AzValue::Asset(asset)) => Some(asset),
+                    _ => None,
+                },
+                instances: field(reference, "Instances")
+                    .map(|instances| {
+                        instances
+                            .elements
+                            .iter()
+                            .map(SliceInstance::from_element)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Entities whose transform parent is `id`.
+    pub fn children(&self, id: EntityId) -> impl Iterator<Item = &Entity<'a>> {
+        self.entities
+            .iter()
+            .filter(move |entity| entity.parent() == Some(id))
+    }
+}
+
+impl<'a> Entity<'a> {
+    pub fn from_element(element: &'a Element) -> Self {
+        let components = field(element, "Components")
+            .map(|components| {
+                components
+                    .elements
+                    .iter()
+                    .map(Component::from_element)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            element,
+            id: field(element, "Id").and_then(EntityId::from_element),
+            name: field(element, "Name")
+                .and_then(Element::value)
+                .unwrap_or_default(),
+            components,
+        }
+    }
+
+    /// The first component with this type name.
+    pub fn component(&self, type_name: &str) -> Option<&Component<'a>> {
+        self.components
+            .iter()
+            .find(|component| component.type_name == type_name)
+    }
+
+    pub fn transform(&self) -> Option<TransformComponent> {
+        self.components.iter().find_map(Component::transform)
+    }
+
+    /// The parent in the transform hierarchy.
+    pub fn parent(&self) -> Option<EntityId> {
+        self.transform()?.parent
+    }
+}
+
+impl<'a> Component<'a> {
+    pub fn from_element(element: &'a Element) -> Self {
+        let id = match field(element, "Id").and_then(Element::az_value) {
+            Some(AzValue::U64(id)) => Some(id),
+            _ => None,
+        };
+        Self {
+            element,
+            id,
+            type_id: element.id,
+            type_name: &element.name,
+        }
+    }
+
+    /// A field of the component or of its base classes.
+    pub fn field(&self, name: &str) -> Option<&'a Element> {
+        field(self.element, name)
+    }
+
+    /// The properties of a `TransformComponent`, `None` for other components.
+    pub fn transform(&self) -> Option<TransformComponent> {
+        if !is_type(self.element, TRANSFORM_COMPONENT, "TransformComponent") {
+            return None;
+        }
+        let transform = |name| match self.field(name)?.az_value()? {
+            AzValue::Transform(m) => Some(m),
+            _ => None,
+        };
+        Some(TransformComponent {
+            parent: self.field("Parent").and_then(EntityId::from_element),
+            world: transform("Transform"),
+            local: transform("LocalTransform"),
+            is_static: match self.field("IsStatic").and_then(Element::az_value) {
+                Some(AzValue::Bool(v)) => Some(v),
+                _ => None,
+            },
+        })
+    }
+}
+
+impl<'a> SliceInstance<'a> {
+    pub fn from_element(element: &'a Element) -> Self {
+        let entity_id_map = match field(element, "EntityIdMap").and_then(Element::container) {
+            Some(Container::Map(pairs)) => pairs
+                .into_iter()
+                .filter_map(|(from, to)| {
+                    Some((EntityId::from_element(from)?, EntityId::from_element(to)?))
+                })
+                .collect(),
+            _ => HashMap::new(),
+        };
+        Self {
+            element,
+            entity_id_map,
+            patch: field(element, "DataPatch").and_then(DataPatch::from_element),
+        }
+    }
+}
+
+impl DataPatch {
+    pub fn from_element(element: &Element) -> Option<Self> {
+        let Some(Container::Map(pairs)) = field(element, "m_patch")?.container() else {
+            return None;
+        };
+        let mut patch = DataPatch::default();
+        for (address, value) in pairs {
+            let address = address
+                .elements
+                .iter()
+                .map(|step| match step.az_value() {
+                    Some(AzValue::U64(step)) => Some(step),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>();
+            match (address, bytes(value)) {
+                (Some(address), Some(value)) if !address.is_empty() => {
+                    patch.patches.push((address, value))
+                }
+                _ => patch.unsupported += 1,
+            }
+        }
+        Some(patch)
+    }
+
+    /// Applies the patches to `root`, the `InstantiatedContainer` of the instance. Returns
+    /// how many could be applied.
+    pub fn apply(&self, root: &mut Element, hashes: Option<&'static LumberyardSource>) -> usize {
+        self.patches
+            .iter()
+            .filter(|(address, value)| apply(root, address, value, hashes).is_some())
+            .count()
+    }
+}
+
+/// `vector<u8>` data, stored as one value or as `element` children.
+fn bytes(element: &Element) -> Option<Vec<u8>> {
+    if let Some(data) = &element.data {
+        return Some(data.clone());
+    }
+    element
+        .elements
+        .iter()
+        .map(|byte| match byte.az_value() {
+            Some(AzValue::U8(byte)) => Some(byte),
+            _ => None,
+        })
+        .collect()
+}
+
+fn apply(
+    root: &mut Element,
+    address: &[u64],
+    value: &[u8],
+    hashes: Option<&'static LumberyardSource>,
+) -> Option<()> {
+    let (last, path) = address.split_last()?;
+    let mut node = root;
+    for step in path {
+        let index = position(node, *step)?;
+        node = &mut node.elements[index];
+    }
+
+    let new = match value.is_empty() {
+        true => None,
+        false => Some(from_reader(&mut &value[..], hashes).ok()?.elements.pop()?),
+    };
+    match (position(node, *last), new) {
+        (Some(index), Some(mut new)) => {
+            let old = &node.elements[index];
+            (new.name_crc, new.field) = (old.name_crc, old.field.clone());
+            node.elements[index] = new;
+        }
+        (Some(index), None) => {
+            node.elements.remove(index);
+        }
+        (None, Some(mut new)) => {
+            let name = match node.container() {
+                Some(_) => field_crc("element"),
+                None => u32::try_from(*last).ok()?,
+            };
+            new.name_crc = Some(name);
+            new.field = hashes.and_then(|h| h.crcs.get(&name).cloned());
+            node.elements.push(new);
+        }
+        (None, None) => return None,
+    }
+    Some(())
+}
+
+/// The child `step` addresses: the one with that persistent id, field name crc or index.
+fn position(node: &Element, step: u64) -> Option<usize> {
+    let children = &node.elements;
+    children
+        .iter()
+        .position(|child| persistent_id(child) == Some(step))
+        .or_else(|| {
+            children
+                .iter()
+                .position(|child| child.name_crc.map(u64::from) == Some(step))
+        })
+        .or_else(|| {
+            node.container()
+                .and_then(|_| usize::try_from(step).ok())
+                .filter(|index| *index < children.len())
+        })
+}
+
+/// Entity id of entities, component id of components.
+pub(crate) fn persistent_id(element: &Element) -> Option<u64> {
+    if element.name_crc != Some(field_crc("element")) {
+        return None;
+    }
+    let id = field(element, "Id")?;
+    match id.az_value() {
+        Some(AzValue::U64(id)) => Some(id),
+        _ => EntityId::from_element(id).map(|id| id.0),
+    }
+}
+
+/// Replaces the entity ids in `element` found in `map`.
+fn remap(element: &mut Element, map: &HashMap<EntityId, EntityId>) {
+    if is_type(element, ENTITY_ID, "EntityId") {
+        let crc = field_crc("id");
+        if let Some(id) = element
+            .elements
+            .iter_mut()
+            .find(|e| e.name_crc == Some(crc))
+        {
+            if let Some(AzValue::U64(from)) = id.az_value() {
+                if let Some(to) = map.get(&EntityId(from)) {
+                    id.data = Some(to.0.to_be_bytes().to_vec());
+                }
+            }
+        }
+        return;
+    }
+    for child in &mut element.elements {
+        remap(child, map);
+    }
+}
+
+/// Flattens slices, loading the slices they instance with `load`. Each referenced slice is
+/// loaded and flattened once.
+pub struct SliceResolver<F> {
+    load: F,
+    hashes: Option<&'static LumberyardSource>,
+    cache: HashMap<Uuid, Vec<FlatEntity>>,
+    loading: HashSet<Uuid>,
+    /// What couldn't be loaded or applied, as it happened.
+    pub errors: Vec<String>,
+}
+
+impl<F> SliceResolver<F>
+where
+    F: FnMut(&AssetRef) -> io::Result<ObjectStream>,
+{
+    pub fn new(hashes: Option<&'static LumberyardSource>, load: F) -> Self {
+        Self {
+            load,
+            hashes,
+            cache: HashMap::new(),
+            loading: HashSet::new(),
+            errors: vec![],
+        }
+    }
+
+    /// The entities of `stream` and of every slice instance in it, recursively.
+    pub fn flatten(&mut self, stream: &ObjectStream) -> Vec<FlatEntity> {
+        match stream.slice() {
+            Some(slice) => self.flatten_slice(&slice, 0),
+            None => vec![],
+        }
+    }
+
+    fn flatten_slice(&mut self, slice: &Slice, depth: usize) -> Vec<FlatEntity> {
+        let mut entities = slice
+            .entities
+            .iter()
+            .map(|entity| FlatEntity {
+                element: entity.element.clone(),
+                source: vec![],
+            })
+            .collect::<Vec<_>>();
+
+        for reference in slice.references() {
+            let Some(asset) = &reference.asset else {
+                self.errors.push("slice reference without an asset".into());
+                continue;
+            };
+            let Some(base) = self.base(asset, depth) else {
+                continue;
+            };
+            for instance in &reference.instances {
+                entities.extend(self.instantiate(asset, &base, instance));
+            }
+            self.cache.insert(asset.guid, base);
+        }
+        entities
+    }
+
+    /// The flattened entities of a referenced slice.
+    fn base(&mut self, asset: &AssetRef, depth: usize) -> Option<Vec<FlatEntity>> {
+        if let Some(base) = self.cache.remove(&asset.guid) {
+            return Some(base);
+        }
+        if depth >= MAX_DEPTH || !self.loading.insert(asset.guid) {
+            self.errors
+                .push(format!("{}: slice instances itself, skipped", asset.hint));
+            return None;
+        }
+        let base = match (self.load)(asset) {
+            Ok(stream) => match stream.slice() {
+                Some(slice) => Some(self.flatten_slice(&slice, depth + 1)),
+                None => {
+                    self.errors.push(format!("{}: not a slice", asset.hint));
+                    None
+                }
+            },
+            Err(e) => {
+                self.errors.push(format!("{}: {e}", asset.hint));
+                None
+            }
+        };
+        self.loading.remove(&asset.guid);
+        base
+    }
+
+    fn instantiate(
+        &mut self,
+        asset: &AssetRef,
+        base: &[FlatEntity],
+        instance: &SliceInstance,
+    ) -> Vec<FlatEntity> {
+        let mut container = Element {
+            elements: vec![Element {
+                name_crc: Some(field_crc("Entities")),
+                field: Some("Entities".into()),
+                elements: base.iter().map(|entity| entity.element.clone()).collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        remap(&mut container, &instance.entity_id_map);
+        if let Some(patch) = &instance.patch {
+            let applied = patch.apply(&mut container, self.hashes);
+            let skipped = patch.patches.len() - applied + patch.unsupported;
+            if skipped > 0 {
+                self.errors
+                    .push(format!("{}: {skipped} patches not applied", asset.hint));
+            }
+        }
+
+        // entities added by patches have no source yet
+        let sources = base
+            .iter()
+            .filter_map(|entity| {
+                let id = Entity::from_element(&entity.element).id?;
+                let id = instance.entity_id_map.get(&id).copied().unwrap_or(id);
+                Some((id, &entity.source))
+            })
+            .collect::<HashMap<_, _>>();
+        let entities = container.elements.pop().map(|e| e.elements);
+        entities
+            .unwrap_or_default()
+            .into_iter()
+            .map(|element| {
+                let mut source = vec![asset.hint.clone()];
+                if let Some(inner) = Entity::from_element(&element)
+                    .id
+                    .and_then(|id| sources.get(&id))
+                {
+                    source.extend(inner.iter().cloned());
+                }
+                FlatEntity { element, source }
+            })
+            .collect()
+    }
+}
+
+fn is_type(element: &Element, id: Uuid, name: &str) -> bool {
+    element.id == id || element.name == name
+}
+
+/// The child named `name`, looking into base classes too.
+pub(crate) fn field<'e>(element: &'e Element, name: &str) -> Option<&'e Element> {
+    let crc = field_crc(name);
+    element
+        .elements
+        .iter()
+        .find_map(|child| match child.name_crc {
+            Some(c) if c == crc => Some(child),
+            Some(c) if BASE_CLASSES.contains(&c) => field(child, name),
+            _ => None,
+        })
+}
+
+pub(crate) fn find<'e, F>(element: &'e Element, predicate: &F) -> Option<&'e Element>
+where
+    F: Fn(&Element) -> bool,
+{
+    if predicate(element) {
+        return Some(element);
+    }
+    element
+        .elements
+        .iter()
+        .find_map(|child| find(child, predicate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element;
+    use utils::types::{
+        ASSET, AZSTD_LIST, AZSTD_PAIR, AZSTD_STRING, AZSTD_UNORDERED_MAP, AZSTD_VECTOR, AZ_U64,
+        BOOL, TRANSFORM,
+    };
+
+    fn entity_id(field: &str, id: u64) -> Element {
+        element(
+            field,
+            ENTITY_ID,
+            None,
+            vec![element(
+                "id",
+                AZ_U64,
+                Some(id.to_be_bytes().to_vec()),
+                vec![],
+            )],
+        )
+    }
+
+    fn entity(id: u64, name: &str, parent: Option<u64>, x: f32) -> Element {
+        let mut tm = [0f32; 12];
+        (tm[0], tm[5], tm[10], tm[3]) = (1., 1., 1., x);
+        let tm = tm.iter().flat_map(|f| f.to_be_bytes()).collect::<Vec<_>>();
+        let mut transform = vec![
+            element(
+                "BaseClass1",
+                Uuid::from_u128(9),
+                None,
+                vec![element(
+                    "Id",
+                    AZ_U64,
+                    Some(7u64.to_be_bytes().to_vec()),
+                    vec![],
+                )],
+            ),
+            element("Transform", TRANSFORM, Some(tm.clone()), vec![]),
+            element("LocalTransform", TRANSFORM, Some(tm), vec![]),
+        ];
+        if let Some(parent) = parent {
+            transform.push(entity_id("Parent", parent));
+        }
+        element(
+            "element",
+            ENTITY,
+            None,
+            vec![
+                entity_id("Id", id),
+                element("Name", AZSTD_STRING, Some(name.into()), vec![]),
+                element(
+                    "Components",
+                    AZSTD_VECTOR,
+                    None,
+                    vec![
+                        element("element", Uuid::from_u128(8), None, vec![]),
+                        element("element", TRANSFORM_COMPONENT, None, transform),
+                    ],
+                ),
+            ],
+        )
+    }
+
+    fn slice_stream(entities: Vec<Element>, prefabs: Vec<Element>) -> ObjectStream {
+        let slice_component = element(
+            "element",
+            SLICE_COMPONENT,
+            None,
+            vec![
+                element("Entities", AZSTD_VECTOR, None, entities),
+                element("Prefabs", AZSTD_LIST, None, prefabs),
+                element("IsDynamic", BOOL, Some(vec![1]), vec![]),
+            ],
+        );
+        ObjectStream {
+            version: 3,
+            elements: vec![element(
+                "",
+                ENTITY,
+                None,
+                vec![element(
+                    "Components",
+                    AZSTD_VECTOR,
+                    None,
+                    vec![slice_component],
+                )],
+            )],
+            ..Default::default()
+        }
+    }
+
+    fn pair(key: Element, value: Element) -> Element {
+        let (mut key, mut value) = (key, value);
+        key.name_crc = Some(field_crc("value1"));
+        value.name_crc = Some(field_crc("value2"));
+        element("element", AZSTD_PAIR, None, vec![key, value])
+    }
+
+    fn patch(address: &[u64], value: Option<Element>) -> Element {
+        let address = address
+            .iter()
+            .map(|step| element("element", AZ_U64, Some(step.to_be_bytes().to_vec()), vec![]))
+            .collect();
+        let mut bytes = vec![];
+        if let Some(value) = value {
+            let mut stream = ObjectStream {
+                version: 3,
+                elements: vec![value],
+                ..Default::default()
+            };
+            stream.elements.iter_mut().for_each(Element::update_flags);
+            stream.to_writer(&mut bytes).unwrap();
+        }
+        pair(
+            element("", AZSTD_VECTOR, None, address),
+            element("", Uuid::from_u128(10), Some(bytes), vec![]),
+        )
+    }
+
+    #[test]
+    fn slice() {
+        let stream = slice_stream(
+            vec![entity(1, "Root", None, 0.), entity(2, "Tree", Some(1), 5.)],
+            vec![],
+        );
+
+        let slice = stream.slice().unwrap();
+        assert!(slice.is_dynamic);
+        assert_eq!(slice.entities.len(), 2);
+        let tree = slice.entity(EntityId(2)).unwrap();
+        assert_eq!(tree.name, "Tree");
+        assert_eq!(tree.components.len(), 2);
+        assert_eq!(tree.components[1].id, Some(7));
+        assert_eq!(tree.parent(), Some(EntityId(1)));
+        let transform = tree.transform().unwrap();
+        assert_eq!(transform.local_translation(), Some([5., 0., 0.]));
+        assert_eq!(slice.entity(EntityId(1)).unwrap().parent(), None);
+        assert_eq!(
+            slice
+                .children(EntityId(1))
+                .map(|e| e.name.as_str())
+                .collect::<Vec<_>>(),
+            ["Tree"]
+        );
+    }
+
+    #[test]
+    fn flatten() {
+        let mut base = slice_stream(vec![entity(1, "Tree", None, 1.)], vec![]);
+        base.elements.iter_mut().for_each(Element::update_flags);
+        let mut buf = vec![];
+        base.to_writer(&mut buf).unwrap();
+        let asset = AssetRef {
+            guid: Uuid::from_u128(20),
+            sub_id: 1,
+            type_id: Uuid::from_u128(21),
+            hint: "slices/tree.slice".into(),
+        };
+        let entities = field_crc("Entities") as u64;
+        let instance = element(
+            "element",
+            Uuid::from_u128(11),
+            None,
+            vec![
+                element(
+                    "EntityIdMap",
+                    AZSTD_UNORDERED_MAP,
+                    None,
+                    vec![pair(entity_id("", 1), entity_id("", 100))],
+                ),
+                element(
+                    "DataPatch",
+                    Uuid::from_u128(12),
+                    None,
+                    vec![element(
+                        "m_patch",
+                        AZSTD_UNORDERED_MAP,
+                        None,
+                        vec![
+                            patch(
+                                &[entities, 100, field_crc("Name") as u64],
+                                Some(element("", AZSTD_STRING, Some(b"Oak".to_vec()), vec![])),
+                            ),
+                            patch(&[entities, 100, field_crc("Components") as u64, 0], None),
+                            patch(&[entities, 5, 6], None),
+                        ],
+                    )],
+                ),
+            ],
+        );
+        let reference = element(
+            "element",
+            Uuid::from_u128(13),
+            None,
+            vec![
+                element(
+                    "Asset",
+                    ASSET,
+                    Some(AzValue::Asset(asset.clone()).to_bytes()),
+                    vec![],
+                ),
+                element("Instances", AZSTD_VECTOR, None, vec![instance]),
+            ],
+        );
+        let top = slice_stream(vec![entity(10, "Root", None, 0.)], vec![reference]);
+
+        let mut resolver = SliceResolver::new(None, |loaded: &AssetRef| {
+            assert_eq!(loaded, &asset);
+            from_reader(&mut buf.as_slice(), None).map_err(io::Error::other)
+        });
+        let flat = resolver.flatten(&top);
+        assert_eq!(flat.len(), 2);
+        assert!(flat[0].source.is_empty());
+        let oak = flat[1].entity();
+        assert_eq!(oak.id, Some(EntityId(100)));
+        assert_eq!(oak.name, "Oak");
+        assert_eq!(oak.components.len(), 1);
+        assert_eq!(
+            oak.transform().unwrap().local_translation(),
+            Some([1., 0., 0.])
+        );
+        assert_eq!(flat[1].source, ["slices/tree.slice"]);
+        assert_eq!(
+            resolver.errors,
+            ["slices/tree.slice: 1 patches not applied"]
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Field name crcs and type ids missing from the hash dictionaries, counted over many streams
+//! to see which names are worth recovering first.
+
+use crate::{
+    braced,
+    error::Result,
+    visit::{visit, ElementHeader, Visit, Visitor},
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use utils::lumberyard::LumberyardSource;
+use uuid::Uuid;
+
+/// Asset paths kept per unresolved hash.
+const EXAMPLES: usize = 5;
+
+#[derive(Debug, Default, Clone)]
+pub struct Occurrences {
+    pub count: u64,
+    /// The first few assets it was seen in.
+    pub examples: Vec<String>,
+}
+
+impl Occurrences {
+    fn add(&mut self, count: u64, path: &str) {
+        self.count += count;
+        if self.examples.len() < EXAMPLES && !self.examples.iter().any(|p| p == path) {
+            self.examples.push(path.to_owned());
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Unresolved {
+    pub crcs: HashMap<u32, Occurrences>,
+    pub uuids: HashMap<Uuid, Occurrences>,
+}
+
+impl Unresolved {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.crcs.is_empty() && self.uuids.is_empty()
+    }
+
+    /// Counts the unresolved names of the binary stream in `buf`, `path` is kept as example.
+    pub fn scan(&mut self, path: &str, buf: &[u8], hashes: &LumberyardSource) -> Result<()> {
+        let mut scanner = Scanner {
+            crcs: HashMap::new(),
+            uuids: HashMap::new(),
+        };
+        visit(buf, Some(hashes), &mut scanner)?;
+        for (crc, count) in scanner.crcs {
+            self.crcs.entry(crc).or_default().add(count, path);
+        }
+        for (id, count) in scanner.uuids {
+            self.uuids.entry(id).or_default().add(count, path);
+        }
+        Ok(())
+    }
+
+    pub fn merge(&mut self, other: Unresolved) {
+        fn merge<K: std::hash::Hash + Eq>(
+            into: &mut HashMap<K, Occurrences>,
+            from: HashMap<K, Occurrences>,
+        ) {
+            for (key, other) in from {
+                let occurrences = into.entry(key).or_default();
+                occurrences.count += other.count;
+                for path in &other.examples {
+                    occurrences.add(0, path);
+                }
+            }
+        }
+        merge(&mut self.crcs, other.crcs);
+        merge(&mut self.uuids, other.uuids);
+    }
+
+    /// `{"crcs": [...], "uuids": [...]}`, most seen first.
+    pub fn to_json(&self) -> Value {
+        fn sorted<K>(map: &HashMap<K, Occurrences>, key: impl Fn(&K) -> String) -> Vec<Value> {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, a_seen), (b, b_seen)| {
+                b_seen.count.cmp(&a_seen.count).then(key(a).cmp(&key(b)))
+            });
+            entries
+                .into_iter()
+                .map(|(k, seen)| json!({"hash": key(k), "count": seen.count, "examples": seen.examples}))
+                .collect()
+        }
+        json!({
+            "crcs": sorted(&self.crcs, |crc| format!("0x{crc:08x}")),
+            "uuids": sorted(&self.uuids, braced),
+        })
+    }
+}
+
+/// Counts per stream, so examples are added once per asset.
+struct Scanner {
+    crcs: HashMap<u32, u64>,
+    uuids: HashMap<Uuid, u64>,
+}
+
+impl<'a> Visitor<'a> for Scanner {
+    fn enter_element(&mut self, header: &ElementHeader<'a>, _: usize) -> Visit {
+        if let (Some(crc), None) = (header.name_crc, header.field) {
+            *self.crcs.entry(crc).or_default() += 1;
+        }
+        if header.name.is_none() {
+            *self.uuids.entry(header.id).or_default() += 1;
+        }
+        Visit::Continue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field_crc, Element, ObjectStream};
+
+    #[test]
+    fn unresolved() {
+        let mut root = Element {
+            id: Uuid::from_u128(1),
+            elements: vec![
+                Element {
+                    name_crc: Some(field_crc("m_known")),
+                    id: Uuid::from_u128(2),
+                    ..Default::default()
+                },
+                Element {
+                    name_crc: Some(field_crc("m_unknown")),
+                    id: Uuid::from_u128(2),
+                    ..Default::default()
+                },
+                Element {
+                    name_crc: Some(field_crc("m_unknown")),
+                    id: Uuid::from_u128(2),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        root.update_flags();
+        let mut buf = vec![];
+        ObjectStream {
+            version: 3,
+            elements: vec![root],
+            ..Default::default()
+        }
+        .to_writer(&mut buf)
+        .unwrap();
+        let hashes = LumberyardSource {
+            uuids: [(Uuid::from_u128(1), "Root".to_string())].into(),
+            crcs: [(field_crc("m_known"), "m_known".to_string())].into(),
+        };
+
+        let mut unresolved = Unresolved::new();
+        unresolved.scan("a.slice", &buf, &hashes).unwrap();
+        let mut other = Unresolved::new();
+        other.scan("b.slice", &buf, &hashes).unwrap();
+        other.scan("b.slice", &buf, &hashes).unwrap();
+        unresolved.merge(other);
+
+        let json = unresolved.to_json();
+        assert_eq!(
+            json["crcs"],
+            json!([{
+                "hash": format!("0x{:08x}", field_crc("m_unknown")),
+                "count": 6,
+                "examples": ["a.slice", "b.slice"],
+            }])
+        );
+        assert_eq!(json["uuids"][0]["count"], 9);
+        assert_eq!(json["uuids"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,327 @@
+//! A reverse index of slices: for each component type and each referenced asset, the slices
+//! and entities that use it.
+//!
+//! Only the entities stored in a slice are indexed, instanced slices are indexed as their own
+//! files and show up here as asset references of the `SliceComponent`.
+
+use crate::{
+    braced,
+    slice::{Entity, Slice},
+    Element,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use utils::types::{AssetRef, AzValue};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageIndex {
+    /// By braced component type id.
+    pub components: BTreeMap<String, ComponentUsage>,
+    /// By asset id, the braced guid and the hex sub id.
+    pub assets: BTreeMap<String, AssetUsage>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComponentUsage {
+    /// Empty when the type couldn't be resolved.
+    pub name: String,
+    pub uses: Vec<Usage>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AssetUsage {
+    /// Path hints the asset was referenced with.
+    pub hints: BTreeSet<String>,
+    pub uses: Vec<Usage>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Usage {
+    pub slice: String,
+    /// `None` for references of the slice itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity: Option<u64>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub entity_name: String,
+    /// Type name of the component holding an asset reference.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+}
+
+impl UsageIndex {
+    /// Indexes the entities of `slice`, stored at `path`, and the slices it instances.
+    pub fn add(&mut self, path: &str, slice: &Slice<'_>) {
+        for entity in &slice.entities {
+            self.add_entity(path, entity);
+        }
+        for reference in slice.references() {
+            if let Some(asset) = &reference.asset {
+                self.add_asset(
+                    asset,
+                    Usage {
+                        slice: path.to_owned(),
+                        component: Some("SliceComponent".to_owned()),
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+    }
+
+    fn add_entity(&mut self, path: &str, entity: &Entity<'_>) {
+        let usage = Usage {
+            slice: path.to_owned(),
+            entity: entity.id.map(|id| id.0),
+            entity_name: entity.name.clone(),
+            component: None,
+        };
+        for component in &entity.components {
+            let key = braced(&component.type_id);
+            let components = self.components.entry(key).or_default();
+            if components.name.is_empty() {
+                components.name = component.type_name.to_owned();
+            }
+            components.uses.push(usage.clone());
+
+            let mut assets = vec![];
+            collect_assets(component.element, &mut assets);
+            for asset in assets {
+                let component = match component.type_name {
+                    "" => braced(&component.type_id),
+                    name => name.to_owned(),
+                };
+                self.add_asset(
+                    &asset,
+                    Usage {
+                        component: Some(component),
+                        ..usage.clone()
+                    },
+                );
+            }
+        }
+    }
+
+    fn add_asset(&mut self, asset: &AssetRef, usage: Usage) {
+        let key = format!("{}:{:x}", braced(&asset.guid), asset.sub_id);
+        let assets = self.assets.entry(key).or_default();
+        if !asset.hint.is_empty() {
+            assets.hints.insert(asset.hint.clone());
+        }
+        assets.uses.push(usage);
+    }
+
+    /// Adds the entries of `other`, an index of other slices.
+    pub fn extend(&mut self, other: UsageIndex) {
+        for (key, usage) in other.components {
+            let components = self.components.entry(key).or_default();
+            if components.name.is_empty() {
+                components.name = usage.name;
+            }
+            components.uses.extend(usage.uses);
+        }
+        for (key, usage) in other.assets {
+            let assets = self.assets.entry(key).or_default();
+            assets.hints.extend(usage.hints);
+            assets.uses.extend(usage.uses);
+        }
+    }
+
+    /// Sorts the uses by slice and entity, and drops duplicates.
+    pub fn sort(&mut self) {
+        let uses = self
+            .components
+            .values_mut()
+            .map(|c| &mut c.uses)
+            .chain(self.assets.values_mut().map(|a| &mut a.uses));
+        for uses in uses {
+            uses.sort();
+            uses.dedup();
+        }
+    }
+
+    /// Component types matching `query`: a type id, or a type name with or without its
+    /// namespaces, ignoring case.
+    pub fn components(&self, query: &str) -> Vec<(&str, &ComponentUsage)> {
+        let id = Uuid::parse_str(query).ok();
+        self.components
+            .iter()
+            .filter(|(key, usage)| match id {
+                Some(id) => Uuid::parse_str(key).is_ok_and(|key| key == id),
+                None => {
+                    usage.name.eq_ignore_ascii_case(query)
+                        || usage
+                            .name
+                            .rsplit("::")
+                            .next()
+                            .is_some_and(|name| name.eq_ignore_ascii_case(query))
+                }
+            })
+            .map(|(key, usage)| (key.as_str(), usage))
+            .collect()
+    }
+
+    /// Assets matching `query`: an asset guid, or part of a path hint, ignoring case.
+    pub fn assets(&self, query: &str) -> Vec<(&str, &AssetUsage)> {
+        let guid = query
+            .split(':')
+            .next()
+            .and_then(|g| Uuid::parse_str(g).ok());
+        let query = query.to_lowercase();
+        self.assets
+            .iter()
+            .filter(|(key, usage)| match guid {
+                Some(guid) => {
+                    key.split(':').next().and_then(|g| Uuid::parse_str(g).ok()) == Some(guid)
+                }
+                None => usage
+                    .hints
+                    .iter()
+                    .any(|hint| hint.to_lowercase().contains(&query)),
+            })
+            .map(|(key, usage)| (key.as_str(), usage))
+            .collect()
+    }
+}
+
+/// Asset references in `element` and its descendants, leaving out null ids.
+fn collect_assets(element: &Element, assets: &mut Vec<AssetRef>) {
+    if let Some(AzValue::Asset(asset)) = element.az_value() {
+        if !asset.guid.is_nil() {
+            assets.push(asset);
+        }
+    }
+    for child in &element.elements {
+        collect_assets(child, assets);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        element,
+        slice::{ENTITY, ENTITY_ID, SLICE_COMPONENT},
+        ObjectStream,
+    };
+    use utils::types::{ASSET, AZSTD_STRING, AZSTD_VECTOR, AZ_U64};
+
+    fn component(type_id: u128, name: &str, elements: Vec<Element>) -> Element {
+        let mut component = element("element", Uuid::from_u128(type_id), None, elements);
+        component.name = name.to_owned();
+        component
+    }
+
+    fn asset(field: &str, guid: u128, hint: &str) -> Element {
+        let asset = AzValue::Asset(AssetRef {
+            guid: Uuid::from_u128(guid),
+            sub_id: 2,
+            type_id: Uuid::from_u128(0xFF),
+            hint: hint.to_owned(),
+        });
+        element(field, ASSET, Some(asset.to_bytes()), vec![])
+    }
+
+    fn slice(entities: &[(u64, &str, Vec<Element>)]) -> ObjectStream {
+        let entities = entities
+            .iter()
+            .map(|(id, name, components)| {
+                let id = element(
+                    "Id",
+                    ENTITY_ID,
+                    None,
+                    vec![element(
+                        "id",
+                        AZ_U64,
+                        Some(id.to_be_bytes().to_vec()),
+                        vec![],
+                    )],
+                );
+                element(
+                    "element",
+                    ENTITY,
+                    None,
+                    vec![
+                        id,
+                        element("Name", AZSTD_STRING, Some(name.as_bytes().to_vec()), vec![]),
+                        element("Components", AZSTD_VECTOR, None, components.clone()),
+                    ],
+                )
+            })
+            .collect();
+        let slice = element(
+            "element",
+            SLICE_COMPONENT,
+            None,
+            vec![element("Entities", AZSTD_VECTOR, None, entities)],
+        );
+        ObjectStream {
+            version: 3,
+            elements: vec![element("root", ENTITY, None, vec![slice])],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn usages() {
+        let button = || component(0xB, "UiButtonComponent", vec![]);
+        let mesh = |hint| {
+            component(
+                0xC,
+                "LmbrCentral::MeshComponent",
+                vec![element(
+                    "Mesh",
+                    Uuid::from_u128(0xD),
+                    None,
+                    vec![asset("Asset", 0xA, hint), asset("Material", 0, "")],
+                )],
+            )
+        };
+        let menu = slice(&[(1, "Play", vec![button()]), (2, "Quit", vec![button()])]);
+        let camp = slice(&[(7, "Tent", vec![mesh("objects/tent.cgf")])]);
+
+        let mut index = UsageIndex::default();
+        index.add("ui/menu.slice", &menu.slice().unwrap());
+        let mut other = UsageIndex::default();
+        other.add("slices/camp.dynamicslice", &camp.slice().unwrap());
+        index.extend(other);
+        index.add("ui/menu.slice", &menu.slice().unwrap());
+        index.sort();
+
+        let buttons = index.components("uibuttoncomponent");
+        assert_eq!(buttons.len(), 1);
+        assert_eq!(
+            buttons[0]
+                .1
+                .uses
+                .iter()
+                .map(|u| u.entity)
+                .collect::<Vec<_>>(),
+            [Some(1), Some(2)]
+        );
+        assert_eq!(index.components("MeshComponent").len(), 1);
+        assert_eq!(
+            index.components(&Uuid::from_u128(0xB).braced().to_string())[0].0,
+            buttons[0].0
+        );
+
+        let tents = index.assets("TENT.cgf");
+        assert_eq!(tents.len(), 1);
+        assert_eq!(tents[0].0, format!("{}:2", braced(&Uuid::from_u128(0xA))));
+        assert_eq!(
+            tents[0].1.uses,
+            [Usage {
+                slice: "slices/camp.dynamicslice".to_owned(),
+                entity: Some(7),
+                entity_name: "Tent".to_owned(),
+                component: Some("LmbrCentral::MeshComponent".to_owned()),
+            }]
+        );
+        assert_eq!(index.assets(&Uuid::from_u128(0xA).to_string()).len(), 1);
+        // null asset ids aren't references
+        assert_eq!(index.assets.len(), 1);
+
+        let json = serde_json::to_string(&index).unwrap();
+        assert_eq!(serde_json::from_str::<UsageIndex>(&json).unwrap(), index);
+    }
+}
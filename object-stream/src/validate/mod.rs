@@ -0,0 +1,288 @@
+//! Structural checks of binary streams: flags that disagree with the element contents, values
+//! with the wrong size for their type, types missing from the dictionary and bytes left after
+//! the last element. Everything here still reads, these are hints of writer bugs or of
+//! misunderstood formats.
+
+use crate::{
+    borrowed::{ElementHeader, SliceReader},
+    braced,
+    error::Result,
+    ST_BINARYFLAG_ELEMENT_HEADER, ST_BINARYFLAG_EXTRA_SIZE_FIELD, ST_BINARY_VALUE_SIZE_MASK,
+};
+use serde::Serialize;
+use std::fmt;
+use utils::{lumberyard::LumberyardSource, types::fixed_size};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Anomaly {
+    /// The element header bit of the flags isn't set.
+    MissingHeaderFlag { flags: u8 },
+    /// Size bits without the value bit.
+    SizeWithoutValue { flags: u8 },
+    /// The size isn't stored the way the engine writes it, inline below 7 bytes and in the
+    /// smallest extra size field otherwise.
+    NonCanonicalSize { flags: u8, size: usize },
+    /// A child without a field name.
+    MissingName,
+    /// A fixed size type without a value.
+    MissingValue,
+    /// The value size doesn't match its type.
+    TypeSize { expected: usize, actual: usize },
+    /// The type id isn't in the dictionary.
+    UnknownType { id: Uuid },
+    /// Bytes after the end marker of the root elements.
+    TrailingBytes { count: usize },
+    /// The stream couldn't be read past this point.
+    Parse { reason: String },
+}
+
+impl Anomaly {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Anomaly::MissingHeaderFlag { .. } => "missing_header_flag",
+            Anomaly::SizeWithoutValue { .. } => "size_without_value",
+            Anomaly::NonCanonicalSize { .. } => "non_canonical_size",
+            Anomaly::MissingName => "missing_name",
+            Anomaly::MissingValue => "missing_value",
+            Anomaly::TypeSize { .. } => "type_size",
+            Anomaly::UnknownType { .. } => "unknown_type",
+            Anomaly::TrailingBytes { .. } => "trailing_bytes",
+            Anomaly::Parse { .. } => "parse",
+        }
+    }
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::MissingHeaderFlag { flags } => {
+                write!(f, "flags {flags:#04x} without header bit")
+            }
+            Anomaly::SizeWithoutValue { flags } => {
+                write!(f, "flags {flags:#04x} have a size but no value")
+            }
+            Anomaly::NonCanonicalSize { flags, size } => {
+                write!(f, "{size} byte value stored with flags {flags:#04x}")
+            }
+            Anomaly::MissingName => f.write_str("child without a field name"),
+            Anomaly::MissingValue => f.write_str("fixed size type without a value"),
+            Anomaly::TypeSize { expected, actual } => {
+                write!(f, "{actual} byte value, the type takes {expected}")
+            }
+            Anomaly::UnknownType { id } => write!(f, "unknown type {}", braced(id)),
+            Anomaly::TrailingBytes { count } => write!(f, "{count} bytes after the last element"),
+            Anomaly::Parse { reason } => f.write_str(reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    /// Byte offset of the element (or of the trailing bytes) from the start of the stream.
+    pub offset: u64,
+    /// Field names (or type names, or name CRCs) from the root to the element.
+    pub path: String,
+    #[serde(flatten)]
+    pub anomaly: Anomaly,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "@{}: {}", self.offset, self.anomaly),
+            false => write!(f, "{} @{}: {}", self.path, self.offset, self.anomaly),
+        }
+    }
+}
+
+/// Checks the binary stream in `buf`. Unknown types are only reported with `hashes`. A stream
+/// that can't be read ends with a [`Anomaly::Parse`] finding.
+pub fn validate(buf: &[u8], hashes: Option<&LumberyardSource>) -> Vec<Finding> {
+    let mut validator = Validator {
+        hashes,
+        path: vec![],
+        findings: vec![],
+    };
+    if let Err(e) = validator.stream(buf) {
+        let offset = match &e {
+            crate::Error::Parse { offset, .. } => *offset,
+            _ => 0,
+        };
+        validator.findings.push(Finding {
+            offset,
+            path: String::new(),
+            anomaly: Anomaly::Parse {
+                reason: e.to_string(),
+            },
+        });
+    }
+    validator.findings
+}
+
+struct Validator<'h> {
+    hashes: Option<&'h LumberyardSource>,
+    path: Vec<String>,
+    findings: Vec<Finding>,
+}
+
+impl<'h> Validator<'h> {
+    fn stream(&mut self, buf: &[u8]) -> Result<()> {
+        let mut reader = SliceReader::new(buf, self.hashes)?;
+        while self.element(&mut reader)? {}
+        if reader.remaining() > 0 {
+            self.findings.push(Finding {
+                offset: reader.offset() as u64,
+                path: String::new(),
+                anomaly: Anomaly::TrailingBytes {
+                    count: reader.remaining(),
+                },
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the next element and its children, `false` at the end marker of the parent.
+    fn element(&mut self, reader: &mut SliceReader<'_>) -> Result<bool> {
+        let offset = reader.offset() as u64;
+        let depth = reader.depth();
+        let Some((header, data)) = reader.open()? else {
+            return Ok(false);
+        };
+        self.path.push(header.path_segment());
+        for anomaly in self.check(&header, data, depth) {
+            self.findings.push(Finding {
+                offset,
+                path: self.path.join("/"),
+                anomaly,
+            });
+        }
+        while self.element(reader)? {}
+        reader.close();
+        self.path.pop();
+        Ok(true)
+    }
+
+    fn check(&self, header: &ElementHeader<'_>, data: Option<&[u8]>, depth: usize) -> Vec<Anomaly> {
+        let flags = header.flags;
+        let mut anomalies = vec![];
+        if flags & ST_BINARYFLAG_ELEMENT_HEADER == 0 {
+            anomalies.push(Anomaly::MissingHeaderFlag { flags });
+        }
+        if depth > 0 && header.name_crc.is_none() {
+            anomalies.push(Anomaly::MissingName);
+        }
+        if self.hashes.is_some() && header.name.is_none() {
+            anomalies.push(Anomaly::UnknownType { id: header.id });
+        }
+
+        let size_flags = flags & (ST_BINARYFLAG_EXTRA_SIZE_FIELD | ST_BINARY_VALUE_SIZE_MASK);
+        let Some(data) = data else {
+            if size_flags != 0 {
+                anomalies.push(Anomaly::SizeWithoutValue { flags });
+            }
+            if fixed_size(&header.id).is_some() {
+                anomalies.push(Anomaly::MissingValue);
+            }
+            return anomalies;
+        };
+        if size_flags != crate::size_flags(data.len()) {
+            anomalies.push(Anomaly::NonCanonicalSize {
+                flags,
+                size: data.len(),
+            });
+        }
+        if let Some(expected) = fixed_size(&header.id).filter(|size| *size != data.len()) {
+            anomalies.push(Anomaly::TypeSize {
+                expected,
+                actual: data.len(),
+            });
+        }
+        anomalies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field_crc, Element, ObjectStream};
+    use crate::{ST_BINARYFLAG_HAS_NAME, ST_BINARYFLAG_HAS_VALUE};
+    use utils::types::{FLOAT, VECTOR3};
+
+    #[test]
+    fn validate() {
+        let mut root = Element {
+            id: Uuid::from_u128(1),
+            elements: vec![
+                Element {
+                    name_crc: Some(field_crc("m_scale")),
+                    id: FLOAT,
+                    data: Some(1f32.to_be_bytes().to_vec()),
+                    ..Default::default()
+                },
+                Element {
+                    name_crc: Some(field_crc("m_position")),
+                    id: VECTOR3,
+                    data: Some(vec![0; 8]),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        root.update_flags();
+        let mut buf = vec![];
+        ObjectStream {
+            version: 3,
+            elements: vec![root],
+            ..Default::default()
+        }
+        .to_writer(&mut buf)
+        .unwrap();
+        let scale = 5 + 1 + 16;
+        // m_scale: 4 bytes inline, rewritten with a 1 byte extra size field
+        buf[scale] = ST_BINARYFLAG_ELEMENT_HEADER
+            | ST_BINARYFLAG_HAS_NAME
+            | ST_BINARYFLAG_HAS_VALUE
+            | ST_BINARYFLAG_EXTRA_SIZE_FIELD
+            | 1;
+        buf.insert(scale + 1 + 4 + 16, 4);
+        buf.extend([0xAA, 0xBB]);
+
+        let hashes = LumberyardSource {
+            uuids: [
+                (Uuid::from_u128(1), "Root".to_string()),
+                (FLOAT, "float".to_string()),
+            ]
+            .into(),
+            crcs: Default::default(),
+        };
+        let findings = super::validate(&buf, Some(&hashes));
+        let anomalies = findings
+            .iter()
+            .map(|f| f.anomaly.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            anomalies,
+            [
+                Anomaly::NonCanonicalSize {
+                    flags: buf[scale],
+                    size: 4
+                },
+                Anomaly::UnknownType { id: VECTOR3 },
+                Anomaly::TypeSize {
+                    expected: 12,
+                    actual: 8
+                },
+                Anomaly::TrailingBytes { count: 2 },
+            ]
+        );
+        assert_eq!(
+            findings[2].path,
+            format!("Root/0x{:08x}", field_crc("m_position"))
+        );
+
+        let findings = super::validate(&buf[..buf.len() - 8], None);
+        assert_eq!(findings.last().unwrap().anomaly.kind(), "parse");
+    }
+}
@@ -0,0 +1,202 @@
+//! Event driven reading of binary streams, for scans that only need a few fields.
+//!
+//! [`visit`] walks the stream in document order and calls `enter_element`, then `value` if the
+//! element has data, then the children, then `exit_element`. Nothing is allocated per element:
+//! headers and data borrow from the input buffer and the hash dictionaries.
+
+use crate::{borrowed::SliceReader, error::Result};
+use utils::lumberyard::LumberyardSource;
+
+pub use crate::borrowed::ElementHeader;
+
+/// What the reader does after a callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    Continue,
+    /// From `enter_element`: don't report the value and children of this element, its
+    /// `exit_element` is still called. Same as `Continue` elsewhere.
+    Skip,
+    /// Stop reading, [`visit`] returns right away.
+    Stop,
+}
+
+pub trait Visitor<'a> {
+    /// `depth` is 0 for root elements.
+    fn enter_element(&mut self, header: &ElementHeader<'a>, depth: usize) -> Visit {
+        let _ = (header, depth);
+        Visit::Continue
+    }
+
+    /// The raw data, [`utils::types::AzValue::decode`] turns it into a typed value.
+    fn value(&mut self, header: &ElementHeader<'a>, data: &'a [u8]) -> Visit {
+        let _ = (header, data);
+        Visit::Continue
+    }
+
+    fn exit_element(&mut self, header: &ElementHeader<'a>, depth: usize) -> Visit {
+        let _ = (header, depth);
+        Visit::Continue
+    }
+}
+
+/// Drives `visitor` over a binary stream. Returns the stream version.
+pub fn visit<'a, V>(
+    buf: &'a [u8],
+    hashes: Option<&'a LumberyardSource>,
+    visitor: &mut V,
+) -> Result<u32>
+where
+    V: Visitor<'a>,
+{
+    let mut reader = SliceReader::new(buf, hashes)?;
+    while let Step::Element = visit_element(&mut reader, visitor, true)? {}
+    Ok(reader.version)
+}
+
+enum Step {
+    Element,
+    /// End marker of the parent.
+    End,
+    Stopped,
+}
+
+/// Reads the next element and its children, reporting them to `visitor` if `report`.
+fn visit_element<'a, V>(reader: &mut SliceReader<'a>, visitor: &mut V, report: bool) -> Result<Step>
+where
+    V: Visitor<'a>,
+{
+    let depth = reader.depth();
+    let Some((header, data)) = reader.open()? else {
+        return Ok(Step::End);
+    };
+
+    let mut report_children = report;
+    if report {
+        match visitor.enter_element(&header, depth) {
+            Visit::Stop => return Ok(Step::Stopped),
+            Visit::Skip => report_children = false,
+            Visit::Continue => {
+                if let Some(data) = data {
+                    if visitor.value(&header, data) == Visit::Stop {
+                        return Ok(Step::Stopped);
+                    }
+                }
+            }
+        }
+    }
+
+    loop {
+        match visit_element(reader, visitor, report_children)? {
+            Step::Element => {}
+            Step::End => break,
+            Step::Stopped => return Ok(Step::Stopped),
+        }
+    }
+    reader.close();
+
+    if report && visitor.exit_element(&header, depth) == Visit::Stop {
+        return Ok(Step::Stopped);
+    }
+    Ok(Step::Element)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field_crc, Element, ObjectStream};
+    use utils::types::{AzValue, AZSTD_STRING, INT};
+    use uuid::Uuid;
+
+    fn stream() -> Vec<u8> {
+        let leaf = |field: &str, id, data: Vec<u8>| Element {
+            name_crc: Some(field_crc(field)),
+            id,
+            data: Some(data),
+            ..Default::default()
+        };
+        let entity = |name: &str, x: i32| Element {
+            id: Uuid::from_u128(1),
+            elements: vec![
+                leaf("m_name", AZSTD_STRING, name.as_bytes().to_vec()),
+                Element {
+                    name_crc: Some(field_crc("m_transform")),
+                    id: Uuid::from_u128(2),
+                    elements: vec![leaf("m_x", INT, x.to_be_bytes().to_vec())],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let mut stream = ObjectStream {
+            version: 3,
+            elements: vec![entity("Tree", 1), entity("Rock", 2)],
+            ..Default::default()
+        };
+        stream.elements.iter_mut().for_each(Element::update_flags);
+        let mut buf = vec![];
+        stream.to_writer(&mut buf).unwrap();
+        buf
+    }
+
+    /// Records events, skipping `m_transform` and stopping at the value `stop`.
+    struct Recorder {
+        events: Vec<String>,
+        stop: Option<AzValue>,
+    }
+
+    impl<'a> Visitor<'a> for Recorder {
+        fn enter_element(&mut self, header: &ElementHeader<'a>, depth: usize) -> Visit {
+            self.events.push(format!("enter {depth}"));
+            match header.name_crc == Some(field_crc("m_transform")) {
+                true => Visit::Skip,
+                false => Visit::Continue,
+            }
+        }
+
+        fn value(&mut self, header: &ElementHeader<'a>, data: &'a [u8]) -> Visit {
+            let value = AzValue::decode(&header.id, data);
+            self.events.push(format!("value {value}"));
+            match Some(value) == self.stop {
+                true => Visit::Stop,
+                false => Visit::Continue,
+            }
+        }
+
+        fn exit_element(&mut self, _: &ElementHeader<'a>, depth: usize) -> Visit {
+            self.events.push(format!("exit {depth}"));
+            Visit::Continue
+        }
+    }
+
+    #[test]
+    fn events() {
+        let buf = stream();
+        let mut recorder = Recorder {
+            events: vec![],
+            stop: None,
+        };
+        assert_eq!(visit(&buf, None, &mut recorder).unwrap(), 3);
+        let entity = |name: &str| {
+            [
+                "enter 0".to_string(),
+                "enter 1".into(),
+                format!("value {name}"),
+                "exit 1".into(),
+                "enter 1".into(),
+                "exit 1".into(),
+                "exit 0".into(),
+            ]
+        };
+        assert_eq!(recorder.events, [entity("Tree"), entity("Rock")].concat());
+
+        let mut recorder = Recorder {
+            events: vec![],
+            stop: Some(AzValue::String("Tree".into())),
+        };
+        visit(&buf, None, &mut recorder).unwrap();
+        assert_eq!(recorder.events, ["enter 0", "enter 1", "value Tree"]);
+
+        recorder.stop = None;
+        assert!(visit(&buf[..buf.len() - 10], None, &mut recorder).is_err());
+    }
+}
@@ -5,19 +5,20 @@ mod resources;
 use app::App;
 use assets::assetcatalog::AssetCatalog;
 use cli::{
-    commands::{test::TestCommands, Commands},
+    commands::{scriptcanvas::GraphFormat, test::TestCommands, Commands},
     ARGS,
 };
 use cliclack::{spinner, ProgressBar};
 use distribution::*;
 use file_system::{FileSystem, State};
+use object_stream::schema::SchemaBuilder;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use std::{
     path::PathBuf,
     process::ExitCode,
     sync::{
         atomic::{AtomicU64, AtomicUsize, Ordering},
-        Arc, LazyLock, RwLock,
+        Arc, LazyLock, Mutex, RwLock,
     },
 };
 use tokio::{
@@ -69,6 +70,64 @@ async fn run() -> tokio::io::Result<()> {
                 run_test_distribution(cwd).await?
             }
         },
+        Commands::Pack(pack) => run_pack(&pack.input, &pack.output, pack.convert).await?,
+        Commands::Query(query) => {
+            let cwd = query.input.input.as_ref().unwrap();
+            let filter = query.filter.filter.as_ref();
+            run_query(cwd, filter, &query.expr, query.json).await?
+        }
+        Commands::Schema(schema) => {
+            let cwd = schema.input.input.as_ref().unwrap();
+            let out = schema.output.output.as_ref().unwrap();
+            let filter = schema.filter.filter.as_ref();
+            run_schema(cwd, out, filter).await?
+        }
+        Commands::Slice(slice) => {
+            let cwd = slice.input.input.as_ref().unwrap();
+            run_slice(cwd, &slice.path, slice.json, slice.prefab).await?
+        }
+        Commands::Diff(diff) => {
+            run_diff(diff.input.input.as_ref(), &diff.old, &diff.new, diff.json).await?
+        }
+        Commands::Validate(validate) => {
+            let cwd = validate.input.input.as_ref().unwrap();
+            let filter = validate.filter.filter.as_ref();
+            run_validate(cwd, filter, validate.json).await?
+        }
+        Commands::ScriptCanvas(graph) => {
+            let cwd = graph.input.input.as_ref().unwrap();
+            run_script_canvas(cwd, &graph.path, &graph.format).await?
+        }
+        Commands::Usages(usages) => {
+            let cwd = usages.input.input.as_ref().unwrap();
+            let filter = usages.filter.filter.as_ref();
+            run_usages(
+                cwd,
+                filter,
+                usages.index.as_ref(),
+                usages.rebuild,
+                usages.component.as_ref(),
+                usages.asset.as_ref(),
+                usages.json,
+            )
+            .await?
+        }
+        Commands::Databases(databases) => {
+            let cwd = databases.input.input.as_ref().unwrap();
+            let out = databases.output.output.as_ref().unwrap();
+            run_databases(cwd, out).await?
+        }
+        Commands::Edit(edit) => {
+            let output = edit.output.as_ref().unwrap_or(&edit.file);
+            run_edit(
+                edit.input.input.as_ref(),
+                &edit.file,
+                output,
+                &edit.set,
+                &edit.remove,
+            )
+            .await?
+        }
     };
 
     Ok(())
@@ -86,7 +145,7 @@ async fn initialize(
     let pb = cliclack::spinner();
     pb.start("Initializing Asset Catalog");
     let data = fs.open("assetcatalog.catalog")?;
-    let _catalog = AssetCatalog::try_from(data.as_slice())?;
+    AssetCatalog::init(data.as_slice())?;
     pb.stop("Asset Catalog Initialized");
     Ok(fs)
 }
@@ -127,6 +186,554 @@ async fn run_test_distribution(cwd: &'static PathBuf) -> tokio::io::Result<()> {
     Ok(())
 }
 
+#[instrument]
+async fn run_query(
+    cwd: &'static PathBuf,
+    filter: Option<&String>,
+    expr: &str,
+    json: bool,
+) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(PathBuf::new);
+    // fail on a bad expression before loading the file system
+    object_stream::query::Query::parse(expr)?;
+    let fs = initialize(cwd, &OUT).await?;
+    let default = String::from("**/*.dynamicslice,**/*.slice");
+    let mut files = fs
+        .files(Some(filter.unwrap_or(&default)))
+        .into_keys()
+        .collect::<Vec<_>>();
+    files.sort();
+
+    let mut total = 0;
+    for file_path in files {
+        let data = match fs.open(file_path) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("{}: {}", file_path.display(), e);
+                continue;
+            }
+        };
+        // binary streams are matched in place, text streams have to be parsed into the owned model
+        let matches = match object_stream::StreamTag::detect(&data) {
+            None => continue,
+            Some(object_stream::StreamTag::BINARY) => {
+                object_stream::ObjectStreamRef::parse(&data, Some(&fs.hashes)).and_then(|stream| {
+                    Ok(stream
+                        .select(expr)?
+                        .into_iter()
+                        .map(|m| (m.path.join("/"), m.element.value()))
+                        .collect::<Vec<_>>())
+                })
+            }
+            Some(_) => object_stream::from_reader(&mut data.as_slice(), Some(&fs.hashes)).and_then(
+                |stream| {
+                    Ok(stream
+                        .select(expr)?
+                        .into_iter()
+                        .map(|m| (m.path.join("/"), m.element.value()))
+                        .collect::<Vec<_>>())
+                },
+            ),
+        };
+        let matches = match matches {
+            Ok(matches) => matches,
+            Err(e) => {
+                eprintln!("{}: {}", file_path.display(), e);
+                continue;
+            }
+        };
+        for (path, value) in matches {
+            total += 1;
+            if json {
+                let line = serde_json::json!({
+                    "file": file_path,
+                    "path": path,
+                    "value": value,
+                });
+                println!("{line}");
+            } else {
+                println!(
+                    "{}: {} = {}",
+                    file_path.display(),
+                    path,
+                    value.as_deref().unwrap_or("-")
+                );
+            }
+        }
+    }
+    if !json {
+        cliclack::outro(format!("{total} matches"))?;
+    }
+    Ok(())
+}
+
+#[instrument]
+async fn run_schema(
+    cwd: &'static PathBuf,
+    out: &'static PathBuf,
+    filter: Option<&String>,
+) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(PathBuf::new);
+    let fs = initialize(cwd, &OUT).await?;
+    let default = String::from("**/*");
+    let files = fs
+        .files(Some(filter.unwrap_or(&default)))
+        .into_keys()
+        .collect::<Vec<_>>();
+
+    let builder = task::spawn_blocking(move || {
+        let pb = ProgressBar::new(files.len() as u64);
+        pb.start("Reading ObjectStreams");
+        let builder = Mutex::new(SchemaBuilder::new());
+        files.par_iter().for_each(|file_path| {
+            pb.inc(1);
+            let Ok(data) = fs.open(file_path) else {
+                return;
+            };
+            let added = match object_stream::StreamTag::detect(&data) {
+                None => return,
+                Some(object_stream::StreamTag::BINARY) => {
+                    object_stream::ObjectStreamRef::parse(&data, Some(&fs.hashes))
+                        .map(|stream| builder.lock().unwrap().add_ref(&stream))
+                }
+                Some(_) => object_stream::from_reader(&mut data.as_slice(), Some(&fs.hashes))
+                    .map(|stream| builder.lock().unwrap().add(&stream)),
+            };
+            if let Err(e) = added {
+                eprintln!("{}: {}", file_path.display(), e);
+            }
+        });
+        pb.stop("ObjectStreams read");
+        builder.into_inner().unwrap()
+    })
+    .await?;
+
+    std::fs::create_dir_all(out)?;
+    let mut count = 0;
+    for id in builder.classes() {
+        let Some(schema) = builder.schema(id) else {
+            continue;
+        };
+        let file = std::fs::File::create(out.join(SchemaBuilder::file_name(id)))?;
+        serde_json::to_writer_pretty(file, &schema)?;
+        count += 1;
+    }
+    std::fs::write(out.join("summary.csv"), builder.summary())?;
+
+    cliclack::outro(format!("Wrote {count} schemas to {}.", out.display()))?;
+    Ok(())
+}
+
+#[instrument]
+async fn run_validate(
+    cwd: &'static PathBuf,
+    filter: Option<&String>,
+    json: bool,
+) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(PathBuf::new);
+    let fs = initialize(cwd, &OUT).await?;
+    let default = String::from("**/*");
+    let files = fs
+        .files(Some(filter.unwrap_or(&default)))
+        .into_keys()
+        .collect::<Vec<_>>();
+    let total = files.len();
+
+    let mut reports = task::spawn_blocking(move || {
+        let pb = ProgressBar::new(files.len() as u64);
+        pb.start("Validating ObjectStreams");
+        let reports = Mutex::new(vec![]);
+        files.par_iter().for_each(|file_path| {
+            pb.inc(1);
+            let Ok(data) = fs.open(file_path) else {
+                return;
+            };
+            // text streams have no binary layout to check
+            if object_stream::StreamTag::detect(&data) != Some(object_stream::StreamTag::BINARY) {
+                return;
+            }
+            let findings = object_stream::validate::validate(&data, Some(&fs.hashes));
+            if !findings.is_empty() {
+                reports.lock().unwrap().push((*file_path, findings));
+            }
+        });
+        pb.stop("ObjectStreams validated");
+        reports.into_inner().unwrap()
+    })
+    .await?;
+    reports.sort_by_key(|(file_path, _)| *file_path);
+
+    let mut kinds = std::collections::BTreeMap::<&str, usize>::new();
+    for (file_path, findings) in &reports {
+        let mut counts = std::collections::BTreeMap::<&str, usize>::new();
+        for finding in findings {
+            *counts.entry(finding.anomaly.kind()).or_default() += 1;
+            *kinds.entry(finding.anomaly.kind()).or_default() += 1;
+        }
+        match json {
+            true => println!(
+                "{}",
+                serde_json::json!({"file": file_path, "findings": findings})
+            ),
+            false => {
+                let counts = counts
+                    .iter()
+                    .map(|(kind, count)| format!("{kind} x{count}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                println!("{}: {counts}", file_path.display());
+                if let Some(first) = findings.first() {
+                    println!("    {first}");
+                }
+            }
+        }
+    }
+    if !json {
+        for (kind, count) in &kinds {
+            cliclack::log::info(format!("{kind}: {count}"))?;
+        }
+        cliclack::outro(format!("{} of {total} files with anomalies", reports.len()))?;
+    }
+    Ok(())
+}
+
+#[instrument]
+async fn run_usages(
+    cwd: &'static PathBuf,
+    filter: Option<&String>,
+    index_path: Option<&PathBuf>,
+    rebuild: bool,
+    component: Option<&String>,
+    asset: Option<&String>,
+    json: bool,
+) -> tokio::io::Result<()> {
+    let cached = index_path.filter(|path| !rebuild && path.exists());
+    let index = match cached {
+        Some(path) => serde_json::from_slice(&std::fs::read(path)?)?,
+        None => {
+            static OUT: LazyLock<PathBuf> = LazyLock::new(PathBuf::new);
+            let fs = initialize(cwd, &OUT).await?;
+            let default = String::from("**/*.slice,**/*.dynamicslice");
+            let files = fs
+                .files(Some(filter.unwrap_or(&default)))
+                .into_keys()
+                .collect::<Vec<_>>();
+
+            let index = task::spawn_blocking(move || {
+                let pb = ProgressBar::new(files.len() as u64);
+                pb.start("Indexing slices");
+                let index = Mutex::new(object_stream::usage::UsageIndex::default());
+                files.par_iter().for_each(|file_path| {
+                    pb.inc(1);
+                    let Ok(data) = fs.open(file_path) else {
+                        return;
+                    };
+                    let Ok(stream) =
+                        object_stream::from_reader(&mut data.as_slice(), Some(&fs.hashes))
+                    else {
+                        return;
+                    };
+                    let Some(slice) = stream.slice() else {
+                        return;
+                    };
+                    let mut usages = object_stream::usage::UsageIndex::default();
+                    usages.add(&file_path.to_string_lossy(), &slice);
+                    index.lock().unwrap().extend(usages);
+                });
+                pb.stop(format!("{} slices indexed", files.len()));
+                let mut index = index.into_inner().unwrap();
+                index.sort();
+                index
+            })
+            .await?;
+            if let Some(path) = index_path {
+                std::fs::write(path, serde_json::to_vec(&index)?)?;
+                cliclack::log::info(format!("Wrote {}", path.display()))?;
+            }
+            index
+        }
+    };
+
+    let print = |kind: &str, key: &str, name: String, uses: &[object_stream::usage::Usage]| {
+        if json {
+            let line = serde_json::json!({kind: key, "name": name, "uses": uses});
+            println!("{line}");
+            return;
+        }
+        let slices = uses
+            .iter()
+            .map(|usage| &usage.slice)
+            .collect::<std::collections::HashSet<_>>();
+        println!(
+            "{name} {key}: {} uses in {} slices",
+            uses.len(),
+            slices.len()
+        );
+        for usage in uses {
+            let entity = match usage.entity {
+                Some(id) => format!(" {} ({id})", usage.entity_name),
+                None => String::new(),
+            };
+            let component = usage
+                .component
+                .as_ref()
+                .map(|component| format!(" [{component}]"))
+                .unwrap_or_default();
+            println!("    {}{entity}{component}", usage.slice);
+        }
+    };
+    if let Some(query) = component {
+        for (key, usage) in index.components(query) {
+            print("component", key, usage.name.clone(), &usage.uses);
+        }
+    }
+    if let Some(query) = asset {
+        for (key, usage) in index.assets(query) {
+            let hints = usage.hints.iter().cloned().collect::<Vec<_>>().join(", ");
+            print("asset", key, hints, &usage.uses);
+        }
+    }
+    if !json {
+        cliclack::outro(format!(
+            "{} component types, {} assets",
+            index.components.len(),
+            index.assets.len()
+        ))?;
+    }
+    Ok(())
+}
+
+#[instrument]
+async fn run_databases(cwd: &'static PathBuf, out: &'static PathBuf) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(PathBuf::new);
+    let fs = initialize(cwd, &OUT).await?;
+    let tables = task::spawn_blocking(move || assets::genericassets::tables(fs)).await?;
+
+    std::fs::create_dir_all(out)?;
+    let mut count = 0;
+    for (name, table) in tables {
+        let table = match table {
+            Ok(table) => table,
+            Err(e) => {
+                cliclack::log::warning(format!("{name}: {e}"))?;
+                continue;
+            }
+        };
+        let file = std::fs::File::create(out.join(format!("{name}.json")))?;
+        serde_json::to_writer_pretty(file, &table.to_json())?;
+        std::fs::write(out.join(format!("{name}.csv")), table.to_csv())?;
+        count += 1;
+    }
+
+    cliclack::outro(format!("Wrote {count} databases to {}.", out.display()))?;
+    Ok(())
+}
+
+#[instrument]
+async fn run_slice(
+    cwd: &'static PathBuf,
+    path: &PathBuf,
+    json: bool,
+    prefab: bool,
+) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(PathBuf::new);
+    let fs = initialize(cwd, &OUT).await?;
+    let catalog = AssetCatalog::get().expect("catalog initialized");
+    let (entities, errors) = assets::slice::flatten(catalog, fs, path)?;
+
+    if prefab {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let flat = entities
+            .iter()
+            .map(|flat| flat.entity())
+            .collect::<Vec<_>>();
+        let prefab = object_stream::o3de::prefab(&name, &flat);
+        println!("{}", serde_json::to_string_pretty(&prefab)?);
+        for error in &errors {
+            eprintln!("{error}");
+        }
+        return Ok(());
+    }
+
+    for flat in &entities {
+        let entity = flat.entity();
+        let id = entity.id.map(|id| id.0);
+        let parent = entity.parent().map(|id| id.0);
+        let translation = entity.transform().and_then(|t| t.world_translation());
+        let components = entity
+            .components
+            .iter()
+            .map(|c| match c.type_name {
+                "" => object_stream::braced(&c.type_id),
+                name => name.to_string(),
+            })
+            .collect::<Vec<_>>();
+        if json {
+            let line = serde_json::json!({
+                "id": id,
+                "name": entity.name,
+                "parent": parent,
+                "translation": translation,
+                "components": components,
+                "source": flat.source,
+            });
+            println!("{line}");
+        } else {
+            let parent = parent
+                .map(|parent| format!(" (parent {parent})"))
+                .unwrap_or_default();
+            let at = translation
+                .map(|[x, y, z]| format!(" at {x} {y} {z}"))
+                .unwrap_or_default();
+            let from = match flat.source.is_empty() {
+                true => String::new(),
+                false => format!(" from {}", flat.source.join(" > ")),
+            };
+            println!(
+                "{} {}{parent}{at} [{}]{from}",
+                id.map(|id| id.to_string()).unwrap_or_else(|| "-".into()),
+                entity.name,
+                components.join(", "),
+            );
+        }
+    }
+    for error in &errors {
+        eprintln!("{error}");
+    }
+    if !json {
+        cliclack::outro(format!(
+            "{} entities, {} unresolved",
+            entities.len(),
+            errors.len()
+        ))?;
+    }
+    Ok(())
+}
+
+#[instrument]
+async fn run_script_canvas(
+    cwd: &'static PathBuf,
+    path: &PathBuf,
+    format: &GraphFormat,
+) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(PathBuf::new);
+    let fs = initialize(cwd, &OUT).await?;
+    let data = fs.open(path)?;
+    let stream = object_stream::from_reader(&mut data.as_slice(), Some(&fs.hashes))?;
+    let Some(graph) = stream.script_canvas() else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{} has no Script Canvas graph", path.display()),
+        ));
+    };
+
+    match format {
+        GraphFormat::Dot => {
+            let name = path.file_stem().unwrap_or_default().to_string_lossy();
+            print!("{}", graph.to_dot(&name));
+        }
+        GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+        GraphFormat::Json => println!("{}", serde_json::to_string_pretty(&graph.to_json())?),
+    }
+    Ok(())
+}
+
+#[instrument]
+async fn run_diff(
+    cwd: Option<&'static PathBuf>,
+    old: &PathBuf,
+    new: &PathBuf,
+    json: bool,
+) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(PathBuf::new);
+    let hashes = match cwd {
+        Some(cwd) => Some(&initialize(cwd, &OUT).await?.hashes),
+        None => None,
+    };
+    let read = |path: &PathBuf| -> tokio::io::Result<object_stream::ObjectStream> {
+        let data = std::fs::read(path)?;
+        Ok(object_stream::from_reader(&mut data.as_slice(), hashes)?)
+    };
+    let changes = read(old)?.diff(&read(new)?);
+
+    for change in &changes {
+        match json {
+            true => println!("{}", serde_json::to_string(change)?),
+            false => println!("{change}"),
+        }
+    }
+    if !json {
+        cliclack::outro(format!("{} changes", changes.len()))?;
+    }
+    Ok(())
+}
+
+#[instrument]
+async fn run_edit(
+    cwd: Option<&'static PathBuf>,
+    file: &PathBuf,
+    output: &PathBuf,
+    set: &[String],
+    remove: &[String],
+) -> tokio::io::Result<()> {
+    static OUT: LazyLock<PathBuf> = LazyLock::new(PathBuf::new);
+    let hashes = match cwd {
+        Some(cwd) => Some(&initialize(cwd, &OUT).await?.hashes),
+        None => None,
+    };
+    let data = std::fs::read(file)?;
+    let mut stream = object_stream::from_reader(&mut data.as_slice(), hashes)?;
+
+    // sets always run before removes, as documented on the command
+    for pair in set.chunks(2) {
+        let count = stream.set_value(&pair[0], &pair[1])?;
+        cliclack::log::info(format!("{}: set {count} values", pair[0]))?;
+    }
+    for expr in remove {
+        let count = stream.remove(expr)?;
+        cliclack::log::info(format!("{expr}: removed {count} elements"))?;
+    }
+
+    let mut buf = vec![];
+    stream.to_writer(&mut buf)?;
+    std::fs::write(output, buf)?;
+    cliclack::outro(format!("Wrote {}", output.display()))?;
+    Ok(())
+}
+
+#[instrument]
+async fn run_pack(
+    input: &'static PathBuf,
+    output: &'static PathBuf,
+    convert: bool,
+) -> tokio::io::Result<()> {
+    let start = Instant::now();
+    let pb = cliclack::spinner();
+    pb.start(format!("Packing {}", input.display()));
+
+    let (count, errors) = task::spawn_blocking(move || {
+        let file = std::fs::File::create(output)?;
+        file_system::pak::pack(input, file, convert, |path| {
+            pb.set_message(format!("{}", path.display()));
+        })
+        .inspect(|_| pb.stop(format!("Packed {}", output.display())))
+        .inspect_err(|e| pb.error(e))
+    })
+    .await??;
+
+    for error in &errors {
+        eprintln!("{error}");
+    }
+    cliclack::outro(format!(
+        "Packed {} files in {}, {} skipped.",
+        count,
+        format_duration(start.elapsed()),
+        errors.len(),
+    ))
+    .unwrap();
+    Ok(())
+}
+
 #[instrument]
 async fn run_extract(
     cwd: &'static PathBuf,
@@ -241,6 +848,21 @@ async fn run_extract(
     let processed = processed.load(Ordering::Relaxed);
     let bytes_cloned = Arc::clone(&bytes);
 
+    if let Commands::Extract(extract) = &ARGS.command {
+        if extract.objectstream.objectstream_unresolved {
+            let unresolved = fs.unresolved.lock().unwrap();
+            let path = out.join("unresolved.json");
+            std::fs::create_dir_all(out)?;
+            std::fs::write(&path, serde_json::to_string_pretty(&unresolved.to_json())?)?;
+            cliclack::log::info(format!(
+                "{} unresolved field names and {} unresolved type ids, see {}",
+                unresolved.crcs.len(),
+                unresolved.uuids.len(),
+                path.display()
+            ))?;
+        }
+    }
+
     cliclack::outro(format!(
         "Processed {}/{} files in {}. Bytes: {}",
         processed,
@@ -1,8 +1,9 @@
-use std::array::TryFromSliceError;
-
-use serde_json::{json, Value};
 use uuid::Uuid;
 
+mod value;
+
+pub use value::{AssetRef, AzValue};
+
 pub const CHAR: Uuid = Uuid::from_u128(0x3AB0037F_AF8D_48CE_BCA0_A170D18B2C03);
 pub const SIGNED_CHAR: Uuid = Uuid::from_u128(0xCFD606FE_41B8_4744_B79F_8A6BD97713D8);
 pub const AZ_S8: Uuid = Uuid::from_u128(0x58422C0E_1E47_4854_98E6_34098F6FE12D);
@@ -44,6 +45,7 @@ pub const AZSTD_SHARED_PTR: Uuid = Uuid::from_u128(0xFE61C84E_149D_43FD_88BA_1C3
 pub const AZSTD_INTRUSIVE_PTR: Uuid = Uuid::from_u128(0x530F8502_309E_4EE1_9AEF_5C0456B1F502);
 pub const AZSTD_OPTIONAL: Uuid = Uuid::from_u128(0xAB8C50C0_23A7_4333_81CD_46F648938B1C);
 pub const AZSTD_BASIC_STRING: Uuid = Uuid::from_u128(0xC26397ED_8F60_4DF6_8320_0D0C592DA3CD);
+pub const AZSTD_STRING: Uuid = Uuid::from_u128(0x03AAAB3F_5C47_5A66_9EBC_D5FA4DB353C9);
 pub const AZSTD_CHAR_TRAITS: Uuid = Uuid::from_u128(0x9B018C0C_022E_4BA4_AE91_2C1E8592DBB2);
 pub const AZSTD_BASIC_STRING_VIEW: Uuid = Uuid::from_u128(0xD348D661_6BDE_4C0A_9540_FCEA4522D497);
 pub const AZSTD_FIXED_VECTOR: Uuid = Uuid::from_u128(0x74044B6F_E922_4FD7_915D_EFC5D1DC59AE);
@@ -59,78 +61,37 @@ pub const ASSET: Uuid = Uuid::from_u128(0x77A19D40_8731_4D3C_9041_1B43047366A4);
 
 pub const VECTOR2: Uuid = Uuid::from_u128(0x3D80F623_C85C_4741_90D0_E4E66164E6BF);
 pub const VECTOR3: Uuid = Uuid::from_u128(0x8379EB7D_01FA_4538_B64B_A6543B4BE73D);
+pub const VECTOR4: Uuid = Uuid::from_u128(0x0CE9FA36_1E3A_4C06_9254_B7C73A732053);
 pub const TRANSFORM: Uuid = Uuid::from_u128(0x5D9958E9_9F1E_4985_B532_FFFDE75FEDFD);
 pub const QUATERNION: Uuid = Uuid::from_u128(0x73103120_3DD3_4873_BAB3_9713FA2804FB);
 pub const COLOR: Uuid = Uuid::from_u128(0x7894072A_9050_4F0F_901B_34B1A0D29417);
 pub const MATRIX3X3: Uuid = Uuid::from_u128(0x15A4332F_7C3F_4A58_AC35_50E1CE53FB9C);
+pub const MATRIX4X4: Uuid = Uuid::from_u128(0x157193C7_B673_4A2B_8B43_5681DCC3DEC3);
+pub const AABB: Uuid = Uuid::from_u128(0xA54C2B36_D5B8_46A1_A529_4EBDBD2450E7);
 
-pub fn uuid_data_to_serialize(
-    id: &Uuid,
-    data: &[u8],
-    is_json: bool,
-) -> Result<Value, TryFromSliceError> {
-    let res = match *id {
-        CHAR | AZ_S8 | SIGNED_CHAR => Value::Number(i8::from_be_bytes(data.try_into()?).into()),
-        SHORT => Value::Number(i16::from_be_bytes(data.try_into()?).into()),
-        INT => Value::Number(i32::from_be_bytes(data.try_into()?).into()),
-        LONG | AZ_S64 => Value::Number(i64::from_be_bytes(data.try_into()?).into()),
-
-        UNSIGNED_CHAR => Value::Number(u8::from_be_bytes(data.try_into()?).into()),
-        UNSIGNED_SHORT => Value::Number(u16::from_be_bytes(data.try_into()?).into()),
-        UNSIGNED_INT => Value::Number(u32::from_be_bytes(data.try_into()?).into()),
-        UNSIGNED_LONG | AZ_U64 => Value::Number(u64::from_be_bytes(data.try_into()?).into()),
-
-        FLOAT => json!(format!("{:.7}", f32::from_be_bytes(data.try_into()?))),
-        DOUBLE => json!(format!("{:.7}", f64::from_be_bytes(data.try_into()?))),
-
-        BOOL => Value::Bool(u8::from_be_bytes(data.try_into()?) != 0),
-
-        AZ_UUID => json!(Uuid::from_bytes(data.try_into()?)
-            .braced()
-            .encode_upper(&mut Uuid::encode_buffer())),
-
-        ASSET => {
-            let mut buf = Uuid::encode_buffer();
-            let guid = Uuid::from_bytes(data[0..16].try_into()?)
-                .braced()
-                .encode_upper(&mut buf);
-            let sub_id = u64::from_be_bytes(data[16..32].try_into()?);
-            let mut buf = Uuid::encode_buffer();
-            let _type = Uuid::from_bytes(data[32..48].try_into()?)
-                .braced()
-                .encode_upper(&mut buf);
-            let size = u64::from_be_bytes(data[48..56].try_into()?);
-            let hint = String::from_utf8_lossy(&data[56..]);
-            assert_eq!(hint.len(), size as usize);
-            if is_json {
-                json!({"assetId": json!({ "guid": guid, "subId": sub_id}), "type": _type, "hint": hint})
-            } else {
-                json!(format!(
-                    "id={}:{},type={},hint={{{}}}",
-                    guid, sub_id, _type, hint
-                ))
-            }
-        }
-
-        VECTOR2 | VECTOR3 | TRANSFORM | COLOR | MATRIX3X3 => {
-            assert!(data.len() % 4 == 0);
-            let data = data.chunks_exact(4);
-            let data = data.map(|b| {
-                let num = f32::from_be_bytes(b.try_into().unwrap());
-                format!("{:.7}", num)
-            });
-
-            if is_json {
-                Value::Array(data.map(|v| json!(v)).collect())
-            } else {
-                json!(data.collect::<Vec<_>>().join(" "))
-            }
-        }
+/// A type id the way the engine writes it, braced and uppercase.
+pub fn braced(id: &Uuid) -> String {
+    id.braced()
+        .encode_upper(&mut Uuid::encode_buffer())
+        .to_owned()
+}
 
-        _ => match String::from_utf8(data.into()) {
-            Ok(string) => json!(string),
-            _ => json!(""),
-        },
+/// Number of bytes a value of type `id` always takes, `None` for variable sized types.
+pub fn fixed_size(id: &Uuid) -> Option<usize> {
+    let size = match *id {
+        CHAR | SIGNED_CHAR | AZ_S8 | UNSIGNED_CHAR | BOOL => 1,
+        SHORT | UNSIGNED_SHORT => 2,
+        INT | UNSIGNED_INT | FLOAT | CRC32 => 4,
+        LONG | AZ_S64 | UNSIGNED_LONG | AZ_U64 | DOUBLE => 8,
+        AZ_UUID => 16,
+        VECTOR2 => 8,
+        VECTOR3 => 12,
+        VECTOR4 | QUATERNION | COLOR => 16,
+        AABB => 24,
+        MATRIX3X3 => 36,
+        TRANSFORM => 48,
+        MATRIX4X4 => 64,
+        _ => return None,
     };
-    Ok(res)
+    Some(size)
 }
@@ -0,0 +1,538 @@
+use std::{fmt, io};
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::*;
+
+/// The data of an ObjectStream element, decoded according to its type id.
+///
+/// [`AzValue::decode`] only picks a typed variant when [`AzValue::to_bytes`] and the text form
+/// read back through [`AzValue::parse`] give back the exact same bytes. Anything else (unknown
+/// binary types, unexpected sizes, NaN payloads, strings that aren't plain text) stays
+/// [`AzValue::Bytes`], written as a `0x` prefixed hex string, so conversions are lossless.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AzValue {
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Bool(bool),
+    Uuid(Uuid),
+    Crc32(u32),
+    Asset(AssetRef),
+    Vector2([f32; 2]),
+    Vector3([f32; 3]),
+    Vector4([f32; 4]),
+    /// x, y, z, w
+    Quaternion([f32; 4]),
+    /// The 3x4 matrix, row by row.
+    Transform([f32; 12]),
+    /// r, g, b, a
+    Color([f32; 4]),
+    Matrix3x3([f32; 9]),
+    Matrix4x4([f32; 16]),
+    /// min x, y, z then max x, y, z
+    Aabb([f32; 6]),
+    String(String),
+    Bytes(Vec<u8>),
+}
+
+/// `AZ::Data::Asset` reference: the asset id, its type and the path hint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetRef {
+    pub guid: Uuid,
+    pub sub_id: u32,
+    pub type_id: Uuid,
+    pub hint: String,
+}
+
+impl AzValue {
+    /// Decodes the data of an element of type `id`, falling back to [`AzValue::Bytes`].
+    pub fn decode(id: &Uuid, data: &[u8]) -> Self {
+        match Self::decode_typed(id, data) {
+            Some(value)
+                if Self::parse(id, &Value::String(value.to_text()))
+                    .is_ok_and(|v| v.to_bytes() == data) =>
+            {
+                value
+            }
+            _ => AzValue::Bytes(data.to_vec()),
+        }
+    }
+
+    fn decode_typed(id: &Uuid, data: &[u8]) -> Option<Self> {
+        let value = match *id {
+            CHAR | AZ_S8 | SIGNED_CHAR => AzValue::I8(i8::from_be_bytes(data.try_into().ok()?)),
+            SHORT => AzValue::I16(i16::from_be_bytes(data.try_into().ok()?)),
+            INT => AzValue::I32(i32::from_be_bytes(data.try_into().ok()?)),
+            LONG | AZ_S64 => AzValue::I64(i64::from_be_bytes(data.try_into().ok()?)),
+
+            UNSIGNED_CHAR => AzValue::U8(u8::from_be_bytes(data.try_into().ok()?)),
+            UNSIGNED_SHORT => AzValue::U16(u16::from_be_bytes(data.try_into().ok()?)),
+            UNSIGNED_INT => AzValue::U32(u32::from_be_bytes(data.try_into().ok()?)),
+            UNSIGNED_LONG | AZ_U64 => AzValue::U64(u64::from_be_bytes(data.try_into().ok()?)),
+
+            FLOAT => AzValue::F32(f32::from_be_bytes(data.try_into().ok()?)),
+            DOUBLE => AzValue::F64(f64::from_be_bytes(data.try_into().ok()?)),
+
+            BOOL => match data {
+                [0] => AzValue::Bool(false),
+                [1] => AzValue::Bool(true),
+                _ => return None,
+            },
+
+            AZ_UUID => AzValue::Uuid(Uuid::from_bytes(data.try_into().ok()?)),
+            CRC32 => AzValue::Crc32(u32::from_be_bytes(data.try_into().ok()?)),
+
+            // guid, sub id padded to the 16 byte alignment of AssetId, type, hint size, hint
+            ASSET => {
+                if data.len() < 56 || data[20..32].iter().any(|b| *b != 0) {
+                    return None;
+                }
+                let size = u64::from_be_bytes(data[48..56].try_into().ok()?);
+                if size != (data.len() - 56) as u64 {
+                    return None;
+                }
+                AzValue::Asset(AssetRef {
+                    guid: Uuid::from_bytes(data[0..16].try_into().ok()?),
+                    sub_id: u32::from_be_bytes(data[16..20].try_into().ok()?),
+                    type_id: Uuid::from_bytes(data[32..48].try_into().ok()?),
+                    hint: std::str::from_utf8(&data[56..]).ok()?.to_owned(),
+                })
+            }
+
+            VECTOR2 => AzValue::Vector2(floats(data)?),
+            VECTOR3 => AzValue::Vector3(floats(data)?),
+            VECTOR4 => AzValue::Vector4(floats(data)?),
+            QUATERNION => AzValue::Quaternion(floats(data)?),
+            TRANSFORM => AzValue::Transform(floats(data)?),
+            COLOR => AzValue::Color(floats(data)?),
+            MATRIX3X3 => AzValue::Matrix3x3(floats(data)?),
+            MATRIX4X4 => AzValue::Matrix4x4(floats(data)?),
+            AABB => AzValue::Aabb(floats(data)?),
+
+            _ => match std::str::from_utf8(data) {
+                Ok(string) if is_text(string) && !is_hex(string) => {
+                    AzValue::String(string.to_owned())
+                }
+                _ => return None,
+            },
+        };
+        Some(value)
+    }
+
+    /// Reads a value of type `id` from the XML or JSON formats. Both the text form of
+    /// [`AzValue::to_text`] and the typed form of [`AzValue::to_json`] are accepted, numbers
+    /// can be JSON numbers or strings, and a `0x` hex string is taken as the raw data.
+    pub fn parse(id: &Uuid, value: &Value) -> io::Result<Self> {
+        if let Some(hex) = value.as_str().filter(|s| is_hex(s)) {
+            let data = from_hex(hex)?;
+            return Ok(match (*id, <[u8; 4]>::try_from(data.as_slice())) {
+                (CRC32, Ok(crc)) => AzValue::Crc32(u32::from_be_bytes(crc)),
+                _ => AzValue::Bytes(data),
+            });
+        }
+
+        let value = match *id {
+            CHAR | AZ_S8 | SIGNED_CHAR => AzValue::I8(parse(value)?),
+            SHORT => AzValue::I16(parse(value)?),
+            INT => AzValue::I32(parse(value)?),
+            LONG | AZ_S64 => AzValue::I64(parse(value)?),
+
+            UNSIGNED_CHAR => AzValue::U8(parse(value)?),
+            UNSIGNED_SHORT => AzValue::U16(parse(value)?),
+            UNSIGNED_INT => AzValue::U32(parse(value)?),
+            UNSIGNED_LONG | AZ_U64 => AzValue::U64(parse(value)?),
+
+            FLOAT => AzValue::F32(parse(value)?),
+            DOUBLE => AzValue::F64(parse(value)?),
+
+            BOOL => AzValue::Bool(parse(value)?),
+
+            AZ_UUID => AzValue::Uuid(parse_uuid(value_str(value)?)?),
+            CRC32 => AzValue::Crc32(parse(value)?),
+
+            ASSET => AzValue::Asset(match value {
+                Value::String(s) if s.starts_with('{') => parse_json_asset(&json_str(s)?)?,
+                Value::String(s) => parse_xml_asset(s)?,
+                value => parse_json_asset(value)?,
+            }),
+
+            VECTOR2 => AzValue::Vector2(parse_floats(value)?),
+            VECTOR3 => AzValue::Vector3(parse_floats(value)?),
+            VECTOR4 => AzValue::Vector4(parse_floats(value)?),
+            QUATERNION => AzValue::Quaternion(parse_floats(value)?),
+            TRANSFORM => AzValue::Transform(parse_floats(value)?),
+            COLOR => AzValue::Color(parse_floats(value)?),
+            MATRIX3X3 => AzValue::Matrix3x3(parse_floats(value)?),
+            MATRIX4X4 => AzValue::Matrix4x4(parse_floats(value)?),
+            AABB => AzValue::Aabb(parse_floats(value)?),
+
+            _ => AzValue::String(value_str(value)?.to_owned()),
+        };
+        Ok(value)
+    }
+
+    /// The big endian data the engine stores for this value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            AzValue::I8(v) => v.to_be_bytes().to_vec(),
+            AzValue::I16(v) => v.to_be_bytes().to_vec(),
+            AzValue::I32(v) => v.to_be_bytes().to_vec(),
+            AzValue::I64(v) => v.to_be_bytes().to_vec(),
+            AzValue::U8(v) => v.to_be_bytes().to_vec(),
+            AzValue::U16(v) => v.to_be_bytes().to_vec(),
+            AzValue::U32(v) | AzValue::Crc32(v) => v.to_be_bytes().to_vec(),
+            AzValue::U64(v) => v.to_be_bytes().to_vec(),
+            AzValue::F32(v) => v.to_be_bytes().to_vec(),
+            AzValue::F64(v) => v.to_be_bytes().to_vec(),
+            AzValue::Bool(v) => vec![*v as u8],
+            AzValue::Uuid(v) => v.as_bytes().to_vec(),
+            AzValue::Asset(asset) => {
+                let mut data = Vec::with_capacity(56 + asset.hint.len());
+                data.extend_from_slice(asset.guid.as_bytes());
+                data.extend_from_slice(&asset.sub_id.to_be_bytes());
+                data.extend_from_slice(&[0; 12]);
+                data.extend_from_slice(asset.type_id.as_bytes());
+                data.extend_from_slice(&(asset.hint.len() as u64).to_be_bytes());
+                data.extend_from_slice(asset.hint.as_bytes());
+                data
+            }
+            AzValue::String(v) => v.as_bytes().to_vec(),
+            AzValue::Bytes(v) => v.clone(),
+            floats => floats
+                .floats()
+                .unwrap_or_default()
+                .iter()
+                .flat_map(|f| f.to_be_bytes())
+                .collect(),
+        }
+    }
+
+    /// The components of the math types.
+    pub fn floats(&self) -> Option<&[f32]> {
+        match self {
+            AzValue::Vector2(v) => Some(v),
+            AzValue::Vector3(v) => Some(v),
+            AzValue::Vector4(v) | AzValue::Quaternion(v) | AzValue::Color(v) => Some(v),
+            AzValue::Transform(v) => Some(v),
+            AzValue::Matrix3x3(v) => Some(v),
+            AzValue::Matrix4x4(v) => Some(v),
+            AzValue::Aabb(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The value as the engine writes it in XML streams: floats with 7 decimals (unless that
+    /// loses precision), math types as space separated floats, assets as
+    /// `id={GUID}:subId,type={TYPE},hint={hint}`.
+    pub fn to_text(&self) -> String {
+        match self {
+            AzValue::I8(v) => v.to_string(),
+            AzValue::I16(v) => v.to_string(),
+            AzValue::I32(v) => v.to_string(),
+            AzValue::I64(v) => v.to_string(),
+            AzValue::U8(v) => v.to_string(),
+            AzValue::U16(v) => v.to_string(),
+            AzValue::U32(v) => v.to_string(),
+            AzValue::U64(v) => v.to_string(),
+            AzValue::F32(v) => format_f32(*v),
+            AzValue::F64(v) => format_f64(*v),
+            AzValue::Bool(v) => v.to_string(),
+            AzValue::Uuid(v) => braced(v),
+            AzValue::Crc32(v) => format!("0x{:08x}", v),
+            AzValue::Asset(asset) => format!(
+                "id={}:{},type={},hint={{{}}}",
+                braced(&asset.guid),
+                asset.sub_id,
+                braced(&asset.type_id),
+                asset.hint
+            ),
+            AzValue::String(v) => v.to_owned(),
+            AzValue::Bytes(v) => to_hex(v),
+            floats => floats
+                .floats()
+                .unwrap_or_default()
+                .iter()
+                .map(|f| format_f32(*f))
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+
+    /// The value as typed JSON: numbers, booleans, arrays of floats for the math types and an
+    /// `{"assetId": {"guid", "subId"}, "type", "hint"}` object for assets. Non finite floats
+    /// are strings.
+    pub fn to_json(&self) -> Value {
+        match self {
+            AzValue::I8(v) => json!(v),
+            AzValue::I16(v) => json!(v),
+            AzValue::I32(v) => json!(v),
+            AzValue::I64(v) => json!(v),
+            AzValue::U8(v) => json!(v),
+            AzValue::U16(v) => json!(v),
+            AzValue::U32(v) => json!(v),
+            AzValue::U64(v) => json!(v),
+            AzValue::F32(v) => json_f32(*v),
+            AzValue::F64(v) => match v.is_finite() {
+                true => json!(v),
+                false => json!(v.to_string()),
+            },
+            AzValue::Bool(v) => json!(v),
+            AzValue::Asset(asset) => json!({
+                "assetId": { "guid": braced(&asset.guid), "subId": asset.sub_id },
+                "type": braced(&asset.type_id),
+                "hint": asset.hint,
+            }),
+            AzValue::Uuid(_) | AzValue::Crc32(_) | AzValue::String(_) | AzValue::Bytes(_) => {
+                json!(self.to_text())
+            }
+            floats => Value::Array(
+                floats
+                    .floats()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|f| json_f32(*f))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for AzValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_text())
+    }
+}
+
+fn floats<const N: usize>(data: &[u8]) -> Option<[f32; N]> {
+    if data.len() != N * 4 {
+        return None;
+    }
+    let mut floats = [0.0; N];
+    for (float, bytes) in floats.iter_mut().zip(data.chunks_exact(4)) {
+        *float = f32::from_be_bytes(bytes.try_into().ok()?);
+    }
+    Some(floats)
+}
+
+fn parse_floats<const N: usize>(value: &Value) -> io::Result<[f32; N]> {
+    let floats = match value {
+        Value::String(s) if s.starts_with('[') => json_str(s)?
+            .as_array()
+            .ok_or_else(|| invalid(s))?
+            .iter()
+            .map(parse::<f32>)
+            .collect::<io::Result<Vec<_>>>()?,
+        Value::String(s) => s
+            .split_whitespace()
+            .map(|f| f.parse::<f32>().map_err(|_| invalid(f)))
+            .collect::<io::Result<Vec<_>>>()?,
+        Value::Array(values) => values
+            .iter()
+            .map(parse::<f32>)
+            .collect::<io::Result<Vec<_>>>()?,
+        value => return Err(invalid(value)),
+    };
+    floats.try_into().map_err(|_| invalid(value))
+}
+
+/// Shortest representation that reads back as the same `f32`.
+fn json_f32(num: f32) -> Value {
+    match num.is_finite() {
+        true => num
+            .to_string()
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map_or_else(|| json!(num.to_string()), Value::Number),
+        false => json!(num.to_string()),
+    }
+}
+
+/// `{:.7}` like the engine writes floats, unless that loses precision.
+fn format_f32(num: f32) -> String {
+    let string = format!("{:.7}", num);
+    match string.parse::<f32>() {
+        Ok(parsed) if parsed.to_bits() == num.to_bits() => string,
+        _ => num.to_string(),
+    }
+}
+
+fn format_f64(num: f64) -> String {
+    let string = format!("{:.7}", num);
+    match string.parse::<f64>() {
+        Ok(parsed) if parsed.to_bits() == num.to_bits() => string,
+        _ => num.to_string(),
+    }
+}
+
+fn is_text(string: &str) -> bool {
+    !string
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+}
+
+fn is_hex(string: &str) -> bool {
+    string
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() % 2 == 0 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+fn to_hex(data: &[u8]) -> String {
+    let mut hex = String::with_capacity(2 + data.len() * 2);
+    hex.push_str("0x");
+    for byte in data {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+    hex
+}
+
+fn from_hex(hex: &str) -> io::Result<Vec<u8>> {
+    let digits = &hex.as_bytes()[2..];
+    digits
+        .chunks_exact(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| invalid(hex))
+        })
+        .collect()
+}
+
+fn invalid(value: impl fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid ObjectStream value: {value}"),
+    )
+}
+
+fn value_str(value: &Value) -> io::Result<&str> {
+    value.as_str().ok_or_else(|| invalid(value))
+}
+
+fn json_str(string: &str) -> io::Result<Value> {
+    serde_json::from_str(string).map_err(|_| invalid(string))
+}
+
+fn parse_uuid(string: &str) -> io::Result<Uuid> {
+    Uuid::parse_str(string).map_err(|_| invalid(string))
+}
+
+fn parse<T: std::str::FromStr>(value: &Value) -> io::Result<T> {
+    let string = match value {
+        Value::String(s) => s.to_owned(),
+        Value::Number(_) | Value::Bool(_) => value.to_string(),
+        _ => return Err(invalid(value)),
+    };
+    string.parse().map_err(|_| invalid(value))
+}
+
+fn parse_json_asset(value: &Value) -> io::Result<AssetRef> {
+    let field = |v: Option<&Value>| v.ok_or_else(|| invalid(value)).cloned();
+    let asset_id = field(value.get("assetId"))?;
+    Ok(AssetRef {
+        guid: parse_uuid(value_str(&field(asset_id.get("guid"))?)?)?,
+        sub_id: parse(&field(asset_id.get("subId"))?)?,
+        type_id: parse_uuid(value_str(&field(value.get("type"))?)?)?,
+        hint: value_str(&field(value.get("hint"))?)?.to_owned(),
+    })
+}
+
+/// `id={GUID}:subId,type={TYPE},hint={hint}`
+fn parse_xml_asset(string: &str) -> io::Result<AssetRef> {
+    let (id, rest) = string
+        .strip_prefix("id=")
+        .and_then(|rest| rest.split_once(",type="))
+        .ok_or_else(|| invalid(string))?;
+    let (guid, sub_id) = id.split_once(':').ok_or_else(|| invalid(string))?;
+    let (type_id, hint) = rest.split_once(",hint={").ok_or_else(|| invalid(string))?;
+    let hint = hint.strip_suffix('}').ok_or_else(|| invalid(string))?;
+    Ok(AssetRef {
+        guid: parse_uuid(guid)?,
+        sub_id: sub_id.parse().map_err(|_| invalid(string))?,
+        type_id: parse_uuid(type_id)?,
+        hint: hint.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(id: Uuid, data: &[u8]) -> AzValue {
+        let value = AzValue::decode(&id, data);
+        assert_eq!(value.to_bytes(), data);
+        for text in [Value::String(value.to_text()), value.to_json()] {
+            assert_eq!(
+                AzValue::parse(&id, &text).unwrap().to_bytes(),
+                data,
+                "{text}"
+            );
+        }
+        value
+    }
+
+    #[test]
+    fn typed() {
+        assert_eq!(roundtrip(INT, &(-5i32).to_be_bytes()), AzValue::I32(-5));
+        assert_eq!(roundtrip(BOOL, &[1]), AzValue::Bool(true));
+        assert_eq!(
+            roundtrip(CRC32, &[0xde, 0xad, 0xbe, 0xef]).to_text(),
+            "0xdeadbeef"
+        );
+
+        let float = roundtrip(FLOAT, &0.1f32.to_be_bytes());
+        assert_eq!(float.to_text(), "0.1000000");
+        assert_eq!(float.to_json(), json!(0.1));
+
+        let quat = [0.0f32, 0.0, 0.70710677, 0.70710677];
+        let data = quat
+            .iter()
+            .flat_map(|f| f.to_be_bytes())
+            .collect::<Vec<_>>();
+        assert_eq!(roundtrip(QUATERNION, &data), AzValue::Quaternion(quat));
+
+        let asset = AzValue::Asset(AssetRef {
+            guid: Uuid::from_u128(1),
+            sub_id: 2,
+            type_id: Uuid::from_u128(3),
+            hint: "objects/tree.cgf".into(),
+        });
+        assert_eq!(roundtrip(ASSET, &asset.to_bytes()), asset);
+        assert_eq!(
+            asset.to_text(),
+            "id={00000000-0000-0000-0000-000000000001}:2,\
+             type={00000000-0000-0000-0000-000000000003},hint={objects/tree.cgf}"
+        );
+
+        assert_eq!(
+            roundtrip(AZSTD_STRING, b"Tree"),
+            AzValue::String("Tree".into())
+        );
+    }
+
+    #[test]
+    fn raw_bytes() {
+        // unexpected size, non canonical bool, hex looking string, negative NaN, binary data
+        let nan = (-f32::NAN).to_be_bytes();
+        for (id, data) in [
+            (INT, &[1, 2, 3][..]),
+            (BOOL, &[2]),
+            (AZSTD_STRING, b"0x1234"),
+            (FLOAT, &nan),
+            (VOID, &[0, 159, 146, 150]),
+        ] {
+            assert_eq!(roundtrip(id, data), AzValue::Bytes(data.to_vec()));
+        }
+        assert_eq!(AzValue::Bytes(vec![0, 0xff]).to_text(), "0x00ff");
+    }
+}